@@ -0,0 +1,73 @@
+// ── echo — the 4004 echoes every character typed on stdin ────────────────────
+//
+// Run:
+//   cargo run --example echo
+//
+// Every key is echoed by the ROM as it is typed; Ctrl-D to quit.
+
+use std::thread;
+use std::time::Duration;
+
+use intel_4004::bus::simple::SimpleBus;
+use intel_4004::chips::{Cpu4004, DataRam4002, Rom4001};
+use intel_4004::dev::shared;
+use intel_4004::dev::terminal::Terminal;
+use intel_4004::machine::Machine;
+
+fn main() {
+    // Keys as they are typed, without the host's own echo. Skipped when stdin
+    // is not a terminal, e.g. piped from a file.
+    #[cfg(all(
+        target_os = "linux",
+        any(
+            target_arch = "x86",
+            target_arch = "x86_64",
+            target_arch = "arm",
+            target_arch = "aarch64",
+            target_arch = "riscv32",
+            target_arch = "riscv64"
+        )
+    ))]
+    let _raw = intel_4004::dev::terminal::RawMode::enable().ok();
+    let term = shared(Terminal::stdin());
+    let mut rom = Rom4001::from_bytes(ECHO_ROM);
+    rom.attach_port(term.clone());
+
+    let bus = SimpleBus::new(rom, DataRam4002::default());
+    let mut m = Machine::new(bus);
+    let done = |cpu: &Cpu4004| cpu.pc() == 0x000 && term.borrow().is_closed();
+    // The ROM polls in a tight loop: run it in slices and let the host core
+    // rest in between.
+    while !done(m.cpu()) {
+        let mut budget = 1_000;
+        m.run_until(|cpu| {
+            budget -= 1;
+            budget == 0 || done(cpu)
+        });
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+// ── ROM ───────────────────────────────────────────────────────────────────────
+//
+// Protocol: RDR returns a status nibble (bit 0 = byte available), then the
+// byte's high and low nibbles. WRR writes a byte as high then low nibble.
+//
+// 000  EA        RDR          status
+// 001  14 00     JCN 4H,00H   nothing available → poll again
+// 003  EA B0     RDR; XCH R0  high nibble
+// 005  EA B1     RDR; XCH R1  low nibble
+// 007  A0 E2     LD R0; WRR
+// 009  A1 E2     LD R1; WRR
+// 00B  40 00     JUN 000H
+
+#[rustfmt::skip]
+const ECHO_ROM: &[u8] = &[
+    0xEA,
+    0x14, 0x00,
+    0xEA, 0xB0,
+    0xEA, 0xB1,
+    0xA0, 0xE2,
+    0xA1, 0xE2,
+    0x40, 0x00,
+];
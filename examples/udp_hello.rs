@@ -15,7 +15,8 @@ use std::time::Duration;
 fn main() {
     let dev = UdpDevice::new("0.0.0.0:0", "127.0.0.1:1234")
         .expect("failed to bind UDP socket")
-        .with_interval(Duration::from_secs(1));
+        .with_interval(Duration::from_secs(1))
+        .with_tx_capacity(1);
 
     let mut rom = Rom4001::from_bytes(HELLO_ROM);
     rom.attach_port(dev);
@@ -32,7 +33,8 @@ fn main() {
 //
 // Protocol: first 2 WRR nibbles are the byte count (len_hi, len_lo).
 // Then 2 nibbles per payload byte (high nibble first).
-// The UdpDevice fires send() after the last byte.
+// The UdpDevice queues the message after the last byte and sends one per
+// second; the ROM waits while the status reports the queue FULL (bit 1).
 //
 // Message: "Hi, this is MCS-4\n"  →  18 bytes = 0x12
 //
@@ -58,7 +60,9 @@ fn main() {
 // 040  D2 E2 DD E2   '-' 0x2D
 // 044  D3 E2 D4 E2   '4' 0x34
 // 048  D0 E2 DA E2   '\n' 0x0A
-// 04C  40 00        JUN 000H  → loop
+// 04C  EA F6 F6     RDR; RAR; RAR   FULL → carry
+// 04F  12 4C        JCN C,04CH      wait while the queue is full
+// 051  40 00        JUN 000H  → loop
 
 #[rustfmt::skip]
 const HELLO_ROM: &[u8] = &[
@@ -81,5 +85,7 @@ const HELLO_ROM: &[u8] = &[
     0xD2, 0xE2, 0xDD, 0xE2,  // '-' 0x2D
     0xD3, 0xE2, 0xD4, 0xE2,  // '4' 0x34
     0xD0, 0xE2, 0xDA, 0xE2,  // '\n' 0x0A
+    0xEA, 0xF6, 0xF6,         // RDR; RAR; RAR
+    0x12, 0x4C,               // JCN C,04CH
     0x40, 0x00,               // JUN 000H
 ];
@@ -1,7 +1,13 @@
 pub mod simple;
+pub mod standard;
+
+use crate::chips::{Cpu4004, DataRam4002};
+use crate::dev::{DeviceContext, PortId};
 
 pub trait Bus {
     fn prog_read(&self, addr12: u16) -> u8;
+    /// `WPM`. Buses without writable program memory ignore it.
+    fn prog_write(&mut self, _value: u8) {}
 
     fn data_set_address(&mut self, addr8: u8);
     fn data_select_bank(&mut self, bank: u8);
@@ -16,4 +22,30 @@ pub trait Bus {
     fn rom_port_read(&mut self) -> u8;
 
     fn ram_port_write(&mut self, value: u8);
+
+    /// The port `WRR` and `RDR` reach: the 4001's, unless the bus picks one
+    /// by the `SRC` address.
+    fn rom_port_id(&self) -> PortId {
+        PortId::Rom
+    }
+
+    /// Lets attached devices advance after each instruction.
+    fn tick(&mut self, _cpu: &Cpu4004) {}
+
+    /// Hands every port its device context, see [`IoDevice::connect`](crate::dev::IoDevice::connect).
+    fn connect(&mut self, _ctx: &DeviceContext) {}
+
+    /// The output latch of every I/O port, with a label, for front panels.
+    fn port_latches(&self) -> Vec<(String, u8)> {
+        Vec::new()
+    }
+}
+
+/// Access to memory outside of instructions, for debuggers.
+pub trait Inspect: Bus {
+    fn ram(&self) -> &DataRam4002;
+    fn ram_mut(&mut self) -> &mut DataRam4002;
+
+    /// Patches program memory, even ROM.
+    fn set_prog_byte(&mut self, addr12: u16, value: u8);
 }
@@ -1,5 +1,6 @@
-use crate::bus::Bus;
-use crate::chips::{DataRam4002, Rom4001};
+use crate::bus::{Bus, Inspect};
+use crate::chips::{Cpu4004, DataRam4002, Rom4001};
+use crate::dev::DeviceContext;
 
 pub struct SimpleBus {
     pub prog: Rom4001,
@@ -48,4 +49,35 @@ impl Bus for SimpleBus {
     fn ram_port_write(&mut self, value: u8) {
         self.data.write_port(value);
     }
+
+    fn tick(&mut self, cpu: &Cpu4004) {
+        self.prog.tick(cpu);
+        self.data.tick(cpu);
+    }
+
+    fn connect(&mut self, ctx: &DeviceContext) {
+        self.prog.connect(ctx);
+        self.data.connect(ctx);
+    }
+
+    fn port_latches(&self) -> Vec<(String, u8)> {
+        vec![
+            ("ROM".to_string(), self.prog.port_latch()),
+            ("RAM".to_string(), self.data.port_latch()),
+        ]
+    }
+}
+
+impl Inspect for SimpleBus {
+    fn ram(&self) -> &DataRam4002 {
+        &self.data
+    }
+
+    fn ram_mut(&mut self) -> &mut DataRam4002 {
+        &mut self.data
+    }
+
+    fn set_prog_byte(&mut self, addr12: u16, value: u8) {
+        self.prog.set_byte(addr12, value);
+    }
 }
@@ -0,0 +1,93 @@
+use crate::bus::{Bus, Inspect};
+use crate::chips::{Cpu4004, DataRam4002, Mem4289};
+use crate::dev::{DeviceContext, PortId};
+
+/// Bus for systems using a 4289 and standard memory instead of 4001 ROMs.
+pub struct StandardBus {
+    pub prog: Mem4289,
+    pub data: DataRam4002,
+}
+
+impl StandardBus {
+    pub fn new(prog: Mem4289, data: DataRam4002) -> Self {
+        Self { prog, data }
+    }
+}
+
+impl Bus for StandardBus {
+    fn prog_read(&self, addr12: u16) -> u8 {
+        self.prog.read_byte(addr12)
+    }
+    fn prog_write(&mut self, value: u8) {
+        self.prog.write_program(value);
+    }
+
+    fn data_set_address(&mut self, addr8: u8) {
+        self.prog.set_address(addr8);
+        self.data.set_address(addr8);
+    }
+    fn data_select_bank(&mut self, bank: u8) {
+        self.data.select_bank(bank);
+    }
+
+    fn data_read(&self) -> u8 {
+        self.data.read()
+    }
+    fn data_write(&mut self, value: u8) {
+        self.data.write(value);
+    }
+
+    fn data_read_status(&self, idx: usize) -> u8 {
+        self.data.read_status(idx)
+    }
+    fn data_write_status(&mut self, idx: usize, value: u8) {
+        self.data.write_status(idx, value);
+    }
+
+    fn rom_port_write(&mut self, value: u8) {
+        self.prog.write_port(value);
+    }
+    fn rom_port_read(&mut self) -> u8 {
+        self.prog.read_port()
+    }
+
+    fn ram_port_write(&mut self, value: u8) {
+        self.data.write_port(value);
+    }
+
+    fn rom_port_id(&self) -> PortId {
+        PortId::Io(self.prog.selected_port())
+    }
+
+    fn tick(&mut self, cpu: &Cpu4004) {
+        self.prog.tick(cpu);
+        self.data.tick(cpu);
+    }
+
+    fn connect(&mut self, ctx: &DeviceContext) {
+        self.prog.connect(ctx);
+        self.data.connect(ctx);
+    }
+
+    fn port_latches(&self) -> Vec<(String, u8)> {
+        let mut latches: Vec<_> = (0..16)
+            .map(|port| (format!("IO{port:X}"), self.prog.port_latch(port)))
+            .collect();
+        latches.push(("RAM".to_string(), self.data.port_latch()));
+        latches
+    }
+}
+
+impl Inspect for StandardBus {
+    fn ram(&self) -> &DataRam4002 {
+        &self.data
+    }
+
+    fn ram_mut(&mut self) -> &mut DataRam4002 {
+        &mut self.data
+    }
+
+    fn set_prog_byte(&mut self, addr12: u16, value: u8) {
+        self.prog.set_byte(addr12, value);
+    }
+}
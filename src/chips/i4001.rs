@@ -1,5 +1,5 @@
-use crate::chips::Port;
-use crate::dev::IoDevice;
+use crate::chips::{Cpu4004, Port};
+use crate::dev::{DeviceContext, IoDevice, PortId};
 
 pub struct Rom4001 {
     bytes: [u8; 4096],
@@ -34,11 +34,28 @@ impl Rom4001 {
         self.bytes[(addr12 & 0x0FFF) as usize]
     }
 
+    /// Patches program memory from a debugger, regardless of the mask ROM.
+    pub fn set_byte(&mut self, addr12: u16, value: u8) {
+        self.bytes[(addr12 & 0x0FFF) as usize] = value;
+    }
+
     pub fn write_port(&mut self, value: u8) {
         self.port.write4(value);
     }
 
+    pub fn port_latch(&self) -> u8 {
+        self.port.latch()
+    }
+
     pub fn read_port(&mut self) -> u8 {
         self.port.read4()
     }
+
+    pub fn tick(&mut self, cpu: &Cpu4004) {
+        self.port.tick(cpu);
+    }
+
+    pub fn connect(&mut self, ctx: &DeviceContext) {
+        self.port.connect(ctx.at(PortId::Rom));
+    }
 }
@@ -1,4 +1,4 @@
-use crate::chips::Port;
+use crate::chips::{Cpu4004, Port};
 
 #[derive(Default)]
 pub struct Register {
@@ -43,14 +43,62 @@ impl DataRam4002 {
         self.banks[self.bank as usize][chip][reg].status_characters[idx] = value & 0xF;
     }
 
+    /// Bank selected by the last `DCL`.
+    pub fn bank(&self) -> u8 {
+        self.bank
+    }
+
+    /// Address latched by the last `SRC`.
+    pub fn address(&self) -> u8 {
+        self.addr8
+    }
+
+    /// A character anywhere in RAM, regardless of `DCL`/`SRC`.
+    pub fn character(&self, bank: u8, chip: u8, reg: u8, ch: u8) -> u8 {
+        self.register(bank, chip, reg).characters[(ch & 0xF) as usize]
+    }
+
+    pub fn set_character(&mut self, bank: u8, chip: u8, reg: u8, ch: u8, value: u8) {
+        self.register_mut(bank, chip, reg).characters[(ch & 0xF) as usize] = value & 0xF;
+    }
+
+    /// A status character anywhere in RAM, regardless of `DCL`/`SRC`.
+    pub fn status(&self, bank: u8, chip: u8, reg: u8, idx: u8) -> u8 {
+        self.register(bank, chip, reg).status_characters[(idx & 0x3) as usize]
+    }
+
+    pub fn set_status(&mut self, bank: u8, chip: u8, reg: u8, idx: u8, value: u8) {
+        self.register_mut(bank, chip, reg).status_characters[(idx & 0x3) as usize] = value & 0xF;
+    }
+
     pub fn write_port(&mut self, value: u8) {
         self.port.write4(value);
     }
 
+    pub fn port_latch(&self) -> u8 {
+        self.port.latch()
+    }
+
     pub fn attach_port(&mut self, dev: impl crate::dev::IoDevice + 'static) {
         self.port.attach(Box::new(dev));
     }
 
+    pub fn tick(&mut self, cpu: &Cpu4004) {
+        self.port.tick(cpu);
+    }
+
+    pub fn connect(&mut self, ctx: &crate::dev::DeviceContext) {
+        self.port.connect(ctx.at(crate::dev::PortId::Ram));
+    }
+
+    fn register(&self, bank: u8, chip: u8, reg: u8) -> &Register {
+        &self.banks[(bank & 0x7) as usize][(chip & 0x3) as usize][(reg & 0x3) as usize]
+    }
+
+    fn register_mut(&mut self, bank: u8, chip: u8, reg: u8) -> &mut Register {
+        &mut self.banks[(bank & 0x7) as usize][(chip & 0x3) as usize][(reg & 0x3) as usize]
+    }
+
     fn decode_addr8(&self) -> (usize, usize, usize) {
         let chip = ((self.addr8 >> 6) & 0x3) as usize;
         let reg = ((self.addr8 >> 4) & 0x3) as usize;
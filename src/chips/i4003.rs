@@ -0,0 +1,107 @@
+use crate::dev::IoDevice;
+
+/// Intel 4003 10-bit serial-in, parallel-out shift register.
+///
+/// Attach it to a ROM/RAM port: one port bit carries serial data, another the
+/// clock, and optionally a third the output enable. Data is shifted in on the
+/// rising edge of the clock bit. Several stages can be chained, the serial
+/// output (Q9) of each stage feeding the data input of the next.
+///
+/// Other devices observe the parallel outputs through a shared handle:
+///
+/// ```
+/// use intel_4004::chips::{Rom4001, ShiftRegister4003};
+/// use intel_4004::dev::shared;
+///
+/// let sr = shared(ShiftRegister4003::new(2).with_enable_bit(2));
+/// let mut rom = Rom4001::from_bytes(&[]);
+/// rom.attach_port(sr.clone());
+/// assert_eq!(sr.borrow().len(), 20);
+/// ```
+pub struct ShiftRegister4003 {
+    bits: Vec<bool>, // Q0 of stage 0 first
+    data_bit: u8,
+    clock_bit: u8,
+    enable_bit: Option<u8>,
+    clock: bool,
+    enabled: bool,
+}
+
+impl ShiftRegister4003 {
+    /// Creates a chain of `stages` 4003s with data on bit 1 and clock on bit 0.
+    pub fn new(stages: usize) -> Self {
+        Self {
+            bits: vec![false; stages * 10],
+            data_bit: 1,
+            clock_bit: 0,
+            enable_bit: None,
+            clock: false,
+            enabled: true,
+        }
+    }
+
+    pub fn with_data_bit(mut self, bit: u8) -> Self {
+        self.data_bit = bit & 0x3;
+        self
+    }
+
+    pub fn with_clock_bit(mut self, bit: u8) -> Self {
+        self.clock_bit = bit & 0x3;
+        self
+    }
+
+    /// Gates the parallel outputs with a port bit. Without one, they are always enabled.
+    pub fn with_enable_bit(mut self, bit: u8) -> Self {
+        self.enable_bit = Some(bit & 0x3);
+        self.enabled = false;
+        self
+    }
+
+    /// Total number of outputs (10 per stage).
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    /// Parallel output `n`, counting Q0–Q9 of the first stage, then the next stage.
+    pub fn output(&self, n: usize) -> bool {
+        self.enabled && self.bits.get(n).copied().unwrap_or(false)
+    }
+
+    /// All parallel outputs, in the same order as [`Self::output`].
+    pub fn outputs(&self) -> Vec<bool> {
+        (0..self.bits.len()).map(|n| self.output(n)).collect()
+    }
+
+    /// Serial output of the last stage, available whether or not outputs are enabled.
+    pub fn serial_out(&self) -> bool {
+        self.bits.last().copied().unwrap_or(false)
+    }
+
+    /// Shifts one bit into the chain, as a clock pulse would.
+    pub fn shift_in(&mut self, data: bool) {
+        if !self.bits.is_empty() {
+            self.bits.rotate_right(1);
+            self.bits[0] = data;
+        }
+    }
+}
+
+impl IoDevice for ShiftRegister4003 {
+    fn write4(&mut self, nibble: u8) {
+        let bit = |n: u8| nibble & (1 << n) != 0;
+
+        let clock = bit(self.clock_bit);
+        if clock && !self.clock {
+            self.shift_in(bit(self.data_bit));
+        }
+        self.clock = clock;
+
+        if let Some(enable) = self.enable_bit {
+            self.enabled = bit(enable);
+        }
+    }
+}
@@ -1,7 +1,6 @@
 use crate::bus::Bus;
 use crate::isa::Instruction;
 
-#[derive(Default)]
 pub struct Cpu4004 {
     acc: u8,         // 4-bit accumulator
     cy: u8,          // 1-bit carry flag
@@ -10,6 +9,22 @@ pub struct Cpu4004 {
     stack: [u16; 3], // 12-bit stack (3-level hardware limit)
     sp: usize,       // stack pointer (0–2)
     cycles: u64,     // elapsed clock periods (8 per 1-byte instr, 16 per 2-byte)
+    test: bool,      // TEST pin level (pulled high)
+}
+
+impl Default for Cpu4004 {
+    fn default() -> Self {
+        Self {
+            acc: 0,
+            cy: 0,
+            r: [0; 16],
+            pc: 0,
+            stack: [0; 3],
+            sp: 0,
+            cycles: 0,
+            test: true,
+        }
+    }
 }
 
 impl Cpu4004 {
@@ -32,6 +47,35 @@ impl Cpu4004 {
     pub fn cycles(&self) -> u64 {
         self.cycles
     }
+    pub fn test(&self) -> bool {
+        self.test
+    }
+
+    /// The 3-level return stack and the index of the next free level.
+    pub fn stack(&self) -> ([u16; 3], usize) {
+        (self.stack, self.sp)
+    }
+
+    pub fn set_test(&mut self, level: bool) {
+        self.test = level;
+    }
+
+    pub fn set_acc(&mut self, value: u8) {
+        self.acc = value & 0xF;
+    }
+    pub fn set_cy(&mut self, value: u8) {
+        self.cy = value & 1;
+    }
+    pub fn set_pc(&mut self, addr12: u16) {
+        self.pc = addr12 & 0x0FFF;
+    }
+    pub fn set_reg(&mut self, n: u8, value: u8) {
+        self.r[(n & 0xF) as usize] = value & 0xF;
+    }
+    pub fn set_stack(&mut self, stack: [u16; 3], sp: usize) {
+        self.stack = stack.map(|addr| addr & 0x0FFF);
+        self.sp = sp % 3;
+    }
 
     pub fn step<B: Bus>(&mut self, bus: &mut B) {
         let pc0 = self.pc;
@@ -59,9 +103,12 @@ impl Cpu4004 {
                 let invert = (cond & 0b1000) != 0;
                 let test_acc = (cond & 0b0100) != 0;
                 let test_cy = (cond & 0b0010) != 0;
-                // cond bit 0 = TEST pin (hardware), not emulated
+                let test_pin = (cond & 0b0001) != 0;
 
-                let jump = ((test_acc && self.acc == 0) || (test_cy && self.cy != 0)) ^ invert;
+                let jump = ((test_acc && self.acc == 0)
+                    || (test_cy && self.cy != 0)
+                    || (test_pin && !self.test))
+                    ^ invert;
                 if jump {
                     self.pc = (self.pc & 0x0F00) | addr8 as u16;
                 }
@@ -137,7 +184,7 @@ impl Cpu4004 {
             Instruction::Wrm => bus.data_write(self.acc),
             Instruction::Wmp => bus.ram_port_write(self.acc),
             Instruction::Wrr => bus.rom_port_write(self.acc),
-            Instruction::Wpm => {} // Write Program Memory — not supported in ROM-only config
+            Instruction::Wpm => bus.prog_write(self.acc),
 
             Instruction::Wr0 => bus.data_write_status(0, self.acc),
             Instruction::Wr1 => bus.data_write_status(1, self.acc),
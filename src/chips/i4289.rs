@@ -0,0 +1,159 @@
+use std::ops::Range;
+
+use crate::chips::{Cpu4004, Port};
+use crate::dev::{DeviceContext, IoDevice, PortId};
+
+/// I/O port that holds the high 4 bits of the program memory address used by `WPM`.
+pub const PAGE_PORT: u8 = 0xE;
+/// I/O port that reads program memory back on the 4004, which has no `RPM` instruction.
+pub const READ_PORT: u8 = 0xF;
+
+/// Intel 4289 standard memory interface.
+///
+/// Replaces the 4001s with up to 4 KB of standard memory (1702 EPROMs and/or
+/// RAM) and provides the 16 I/O ports the 4001s used to carry. The upper nibble
+/// of the `SRC` address selects the I/O port, the full 8 bits plus the page
+/// register (port 14) address program memory for `WPM`.
+///
+/// `WPM` and program memory reads alternate between the high and low half of
+/// the addressed byte (the F/L flip-flop), starting with the high half after
+/// each `SRC`.
+pub struct Mem4289 {
+    bytes: [u8; 4096],
+    writable: [bool; 16], // per 256-byte page
+    addr8: u8,            // latch d'adresse (SRC)
+    page: u8,             // high address nibble for WPM/RPM
+    low_half: bool,       // F/L flip-flop
+    ports: [Port; 16],
+}
+
+impl Default for Mem4289 {
+    fn default() -> Self {
+        Self {
+            bytes: [0; 4096],
+            writable: [false; 16],
+            addr8: 0,
+            page: 0,
+            low_half: false,
+            ports: Default::default(),
+        }
+    }
+}
+
+impl Mem4289 {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut mem = Self::default();
+        let len = bytes.len().min(4096);
+        mem.bytes[..len].copy_from_slice(&bytes[..len]);
+        mem
+    }
+
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let data = std::fs::read(path)?;
+        Ok(Self::from_bytes(&data))
+    }
+
+    /// Marks the given 256-byte pages as RAM, writable through `WPM`.
+    pub fn with_ram(mut self, pages: Range<u8>) -> Self {
+        for page in pages.take_while(|&p| p < 16) {
+            self.writable[page as usize] = true;
+        }
+        self
+    }
+
+    /// Loads a 1702 EPROM image (256 bytes) into the given page.
+    pub fn load_eprom(&mut self, page: u8, image: &[u8]) {
+        let base = (page as usize & 0xF) << 8;
+        let len = image.len().min(256);
+        self.bytes[base..base + len].copy_from_slice(&image[..len]);
+    }
+
+    pub fn attach_port(&mut self, port: u8, dev: impl IoDevice + 'static) {
+        self.ports[(port & 0xF) as usize].attach(Box::new(dev));
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn read_byte(&self, addr12: u16) -> u8 {
+        self.bytes[(addr12 & 0x0FFF) as usize]
+    }
+
+    /// Patches program memory from a debugger, regardless of write protection.
+    pub fn set_byte(&mut self, addr12: u16, value: u8) {
+        self.bytes[(addr12 & 0x0FFF) as usize] = value;
+    }
+
+    pub fn set_address(&mut self, addr8: u8) {
+        self.addr8 = addr8;
+        self.low_half = false;
+    }
+
+    pub fn write_port(&mut self, value: u8) {
+        match self.selected_port() {
+            PAGE_PORT => self.page = value & 0xF,
+            port => self.ports[port as usize].write4(value),
+        }
+    }
+
+    /// The last value written to `port`; the page register for [`PAGE_PORT`].
+    pub fn port_latch(&self, port: u8) -> u8 {
+        match port & 0xF {
+            PAGE_PORT => self.page,
+            port => self.ports[port as usize].latch(),
+        }
+    }
+
+    pub fn read_port(&mut self) -> u8 {
+        match self.selected_port() {
+            READ_PORT => self.read_program(),
+            port => self.ports[port as usize].read4(),
+        }
+    }
+
+    pub fn tick(&mut self, cpu: &Cpu4004) {
+        for port in &mut self.ports {
+            port.tick(cpu);
+        }
+    }
+
+    pub fn connect(&mut self, ctx: &DeviceContext) {
+        for (n, port) in self.ports.iter_mut().enumerate() {
+            port.connect(ctx.at(PortId::Io(n as u8)));
+        }
+    }
+
+    /// `WPM`: writes one half of the addressed program memory byte.
+    ///
+    /// Writes to EPROM pages are ignored, but still toggle the F/L flip-flop.
+    pub fn write_program(&mut self, value: u8) {
+        let addr = self.program_address();
+        if self.writable[addr >> 8] {
+            let byte = &mut self.bytes[addr];
+            *byte = if self.low_half {
+                (*byte & 0xF0) | (value & 0xF)
+            } else {
+                (*byte & 0x0F) | ((value & 0xF) << 4)
+            };
+        }
+        self.low_half = !self.low_half;
+    }
+
+    /// `RPM`: reads one half of the addressed program memory byte.
+    pub fn read_program(&mut self) -> u8 {
+        let byte = self.bytes[self.program_address()];
+        let value = if self.low_half { byte & 0xF } else { byte >> 4 };
+        self.low_half = !self.low_half;
+        value
+    }
+
+    /// The I/O port `WRR` and `RDR` reach, from the last `SRC`.
+    pub fn selected_port(&self) -> u8 {
+        self.addr8 >> 4
+    }
+
+    fn program_address(&self) -> usize {
+        ((self.page as usize) << 8) | self.addr8 as usize
+    }
+}
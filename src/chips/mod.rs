@@ -1,26 +1,52 @@
 pub mod i4001;
 pub mod i4002;
+pub mod i4003;
 pub mod i4004;
+pub mod i4289;
 
 pub use i4001::Rom4001;
 pub use i4002::DataRam4002;
+pub use i4003::ShiftRegister4003;
 pub use i4004::Cpu4004;
+pub use i4289::Mem4289;
 
-use crate::dev::IoDevice;
+use crate::dev::{DeviceContext, IoDevice};
 
 #[derive(Default)]
 pub struct Port {
     dev: Option<Box<dyn IoDevice>>,
+    latch: u8,
+    ctx: Option<DeviceContext>,
 }
 
 impl Port {
     pub fn attach(&mut self, dev: Box<dyn IoDevice>) {
-        assert!(self.dev.is_none(), "Port already has a device attached");
+        assert!(
+            self.dev.is_none(),
+            "Port already has a device attached; use a PortSplitter to share it"
+        );
         self.dev = Some(dev);
+        if let (Some(d), Some(ctx)) = (&mut self.dev, &self.ctx) {
+            d.connect(ctx);
+        }
+    }
+
+    /// Hands the device `ctx`, now or when one is attached.
+    pub fn connect(&mut self, ctx: DeviceContext) {
+        if let Some(d) = &mut self.dev {
+            d.connect(&ctx);
+        }
+        self.ctx = Some(ctx);
+    }
+
+    /// The last value written, held on the output pins like the real latch.
+    pub fn latch(&self) -> u8 {
+        self.latch
     }
 
     #[inline]
     pub fn write4(&mut self, value: u8) {
+        self.latch = value & 0x0F;
         if let Some(d) = &mut self.dev {
             d.write4(value & 0x0F);
         }
@@ -30,4 +56,11 @@ impl Port {
     pub fn read4(&mut self) -> u8 {
         self.dev.as_mut().map_or(0, |d| d.read4() & 0x0F)
     }
+
+    #[inline]
+    pub fn tick(&mut self, cpu: &Cpu4004) {
+        if let Some(d) = &mut self.dev {
+            d.tick(cpu);
+        }
+    }
 }
@@ -0,0 +1,599 @@
+//! GDB remote serial protocol stub.
+//!
+//! [`listen`] waits for one client on a TCP port and serves the [`Debugger`]
+//! to it until the client kills (`k`) or detaches (`D`).
+//!
+//! The client has to speak the protocol itself, packet by packet, the way
+//! `tests/gdb.rs` drives the stub; that is the only client it is tested with.
+//! Stock GDB, `gdb-multiarch` included, does not work: it has no 4004
+//! architecture, so it rejects [`target_xml`], which names none.
+//!
+//! The register layout in [`target_xml`] is `acc`, `cy`, `r0`–`r15`,
+//! `pc`, the 3 stack levels `s0`–`s2` and the stack pointer `sp`, in that
+//! order. Nibbles are held one per byte, addresses as 16-bit little-endian.
+//!
+//! The 4004's separate memories appear at distinct addresses:
+//!
+//! ```text
+//! 0x00000-0x00FFF   program memory, one byte per address
+//! 0x10000-0x107FF   RAM characters: bank << 8 | SRC address (chip, reg, char)
+//! 0x20000-0x201FF   RAM status characters: bank << 6 | chip << 4 | reg << 2 | n
+//! ```
+//!
+//! Memory reads and writes (`m`/`M`/`X`), register reads and writes (`g`/`G`/
+//! `p`/`P`), single-step (`s`), continue (`c`, interrupted by Ctrl-C),
+//! software and hardware breakpoints (`Z0`/`Z1`) and, on RAM, write, read and
+//! access watchpoints (`Z2`–`Z4`) are supported.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+use crate::bus::Inspect;
+use crate::debugger::{BreakId, Cell, Debugger, Direction, RamLocation, StopReason, Watch};
+
+pub const PROG_BASE: u32 = 0x0_0000;
+pub const RAM_BASE: u32 = 0x1_0000;
+pub const STATUS_BASE: u32 = 0x2_0000;
+
+/// Instructions run between two checks for Ctrl-C while continuing.
+const CHUNK: usize = 10_000;
+
+/// acc, cy, r0–r15 and sp are 1 byte; pc and s0–s2 are 2.
+const REGISTERS: usize = 23;
+const PC: usize = 18;
+const SP: usize = 22;
+
+/// Target description, served as `target.xml`.
+pub fn target_xml() -> String {
+    let mut regs = String::new();
+    let mut reg = |name: &str, bits: u32, kind: &str| {
+        regs.push_str(&format!(
+            "    <reg name=\"{name}\" bitsize=\"{bits}\" type=\"{kind}\"/>\n"
+        ));
+    };
+    reg("acc", 8, "uint8");
+    reg("cy", 8, "uint8");
+    for n in 0..16 {
+        reg(&format!("r{n}"), 8, "uint8");
+    }
+    reg("pc", 16, "code_ptr");
+    for n in 0..3 {
+        reg(&format!("s{n}"), 16, "code_ptr");
+    }
+    reg("sp", 8, "uint8");
+    format!(
+        "<?xml version=\"1.0\"?>\n\
+         <!DOCTYPE target SYSTEM \"gdb-target.dtd\">\n\
+         <target version=\"1.0\">\n  <feature name=\"org.intel-4004.core\">\n{regs}  </feature>\n</target>\n"
+    )
+}
+
+/// Waits for one client on `addr` and serves it.
+pub fn listen<B: Inspect>(dbg: &mut Debugger<B>, addr: impl ToSocketAddrs) -> io::Result<()> {
+    let (stream, _) = TcpListener::bind(addr)?.accept()?;
+    serve(dbg, stream)
+}
+
+/// Serves one session on `stream`, until the client kills or detaches.
+pub fn serve<B: Inspect>(dbg: &mut Debugger<B>, stream: TcpStream) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    let mut session = Session {
+        dbg,
+        reader: BufReader::new(stream.try_clone()?),
+        writer: stream,
+        acks: true,
+        last: Vec::new(),
+        breakpoints: HashMap::new(),
+        watchpoints: HashMap::new(),
+    };
+    while let Some(packet) = session.read_packet()? {
+        match packet.first() {
+            Some(b'k') => break,
+            Some(b'D') => {
+                session.send(b"OK")?;
+                break;
+            }
+            _ => {
+                let reply = session.handle(&packet);
+                session.send(&reply)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Session<'a, B: Inspect> {
+    dbg: &'a mut Debugger<B>,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    /// Whether packets are acknowledged, until `QStartNoAckMode`.
+    acks: bool,
+    /// Last packet sent, for a `-` asking to send it again.
+    last: Vec<u8>,
+    /// `Z0`/`Z1` breakpoints by kind and address.
+    breakpoints: HashMap<(u8, u32), BreakId>,
+    watchpoints: HashMap<(u8, u32, u32), Vec<BreakId>>,
+}
+
+impl<B: Inspect> Session<'_, B> {
+    /// The next packet's data, or `None` once GDB hangs up.
+    fn read_packet(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            let mut byte = [0];
+            if self.read_exact_or_eof(&mut byte)? {
+                return Ok(None);
+            }
+            match byte[0] {
+                b'$' => {}
+                b'-' => {
+                    let last = std::mem::take(&mut self.last);
+                    self.send(&last)?;
+                    continue;
+                }
+                _ => continue, // '+' acks, stray Ctrl-C
+            }
+            let mut data = Vec::new();
+            if self.reader.read_until(b'#', &mut data)? == 0 {
+                return Ok(None);
+            }
+            data.pop();
+            let mut checksum = [0; 2];
+            if self.read_exact_or_eof(&mut checksum)? {
+                return Ok(None);
+            }
+            let expected = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|s| u8::from_str_radix(s, 16).ok());
+            if self.acks {
+                let ok = expected == Some(sum(&data));
+                self.writer.write_all(if ok { b"+" } else { b"-" })?;
+                if !ok {
+                    continue;
+                }
+            }
+            return Ok(Some(data));
+        }
+    }
+
+    /// Fills `buf`, or returns true at end of stream.
+    fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        match io::Read::read_exact(&mut self.reader, buf) {
+            Ok(()) => Ok(false),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(true),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn send(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut packet = Vec::with_capacity(data.len() + 4);
+        packet.push(b'$');
+        for &b in data {
+            if matches!(b, b'#' | b'$' | b'}' | b'*') {
+                packet.extend([b'}', b ^ 0x20]);
+            } else {
+                packet.push(b);
+            }
+        }
+        let checksum = sum(&packet[1..]);
+        packet.extend(format!("#{checksum:02x}").bytes());
+        self.writer.write_all(&packet)?;
+        self.last = data.to_vec();
+        Ok(())
+    }
+
+    /// Whether GDB sent Ctrl-C, without waiting for it.
+    fn interrupted(&mut self) -> io::Result<bool> {
+        self.reader.get_ref().set_nonblocking(true)?;
+        let interrupted = match self.reader.fill_buf() {
+            Ok(buf) => buf.first() == Some(&0x03),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => false,
+            Err(e) => return Err(e),
+        };
+        if interrupted {
+            self.reader.consume(1);
+        }
+        self.reader.get_ref().set_nonblocking(false)?;
+        Ok(interrupted)
+    }
+
+    /// The reply to `packet`, empty if it is not supported or not text.
+    fn handle(&mut self, packet: &[u8]) -> Vec<u8> {
+        let Some((&cmd, args)) = packet.split_first() else {
+            return Vec::new();
+        };
+        // Binary data; the rest of the protocol is ASCII.
+        if cmd == b'X' {
+            return self.write_binary(args).into_bytes();
+        }
+        let Ok(args) = std::str::from_utf8(args) else {
+            return Vec::new();
+        };
+        let reply = match cmd {
+            b'?' => "S05".to_string(),
+            b'g' => self.read_registers(),
+            b'G' => self.write_registers(args),
+            b'p' => match usize::from_str_radix(args, 16) {
+                Ok(n) if n < REGISTERS => self.register(n),
+                _ => "E00".to_string(),
+            },
+            b'P' => self.write_register(args),
+            b'm' => self.read_memory(args),
+            b'M' => self.write_memory(args),
+            b's' => self.resume(true),
+            b'c' => self.resume(false),
+            b'Z' | b'z' => self.breakpoint(cmd == b'Z', args),
+            b'H' => "OK".to_string(),
+            b'q' => self.query(args),
+            b'Q' if args == "StartNoAckMode" => {
+                self.acks = false;
+                "OK".to_string()
+            }
+            _ => String::new(),
+        };
+        reply.into_bytes()
+    }
+
+    /// A `q` packet, without the `q`.
+    fn query(&mut self, query: &str) -> String {
+        if query.starts_with("Supported") {
+            return "PacketSize=4000;qXfer:features:read+;QStartNoAckMode+".to_string();
+        }
+        if let Some(range) = query.strip_prefix("Xfer:features:read:target.xml:") {
+            let xml = target_xml();
+            let Some((offset, len)) = range.split_once(',') else {
+                return "E00".to_string();
+            };
+            let (Ok(offset), Ok(len)) = (
+                usize::from_str_radix(offset, 16),
+                usize::from_str_radix(len, 16),
+            ) else {
+                return "E00".to_string();
+            };
+            let start = offset.min(xml.len());
+            let end = (start + len).min(xml.len());
+            let more = if end < xml.len() { "m" } else { "l" };
+            return format!("{more}{}", &xml[start..end]);
+        }
+        match query {
+            "Attached" => "1",
+            "C" => "QC1",
+            "fThreadInfo" => "m1",
+            "sThreadInfo" => "l",
+            _ => "",
+        }
+        .to_string()
+    }
+
+    /// Register `n`, as GDB expects it: hex bytes, little-endian.
+    fn register(&self, n: usize) -> String {
+        let cpu = self.dbg.machine().cpu();
+        let (stack, sp) = cpu.stack();
+        match n {
+            0 => format!("{:02x}", cpu.acc()),
+            1 => format!("{:02x}", cpu.cy()),
+            2..=17 => format!("{:02x}", cpu.reg(n as u8 - 2)),
+            PC => hex16(cpu.pc()),
+            19..=21 => hex16(stack[n - 19]),
+            _ => format!("{sp:02x}"),
+        }
+    }
+
+    fn read_registers(&self) -> String {
+        (0..REGISTERS).map(|n| self.register(n)).collect()
+    }
+
+    fn set_register(&mut self, n: usize, bytes: &[u8]) {
+        let cpu = self.dbg.machine_mut().cpu_mut();
+        let byte = bytes.first().copied().unwrap_or(0);
+        let word = u16::from_le_bytes([byte, bytes.get(1).copied().unwrap_or(0)]);
+        match n {
+            0 => cpu.set_acc(byte),
+            1 => cpu.set_cy(byte),
+            2..=17 => cpu.set_reg(n as u8 - 2, byte),
+            PC => cpu.set_pc(word),
+            19..=21 => {
+                let (mut stack, sp) = cpu.stack();
+                stack[n - 19] = word;
+                cpu.set_stack(stack, sp);
+            }
+            _ => {
+                let (stack, _) = cpu.stack();
+                cpu.set_stack(stack, byte as usize);
+            }
+        }
+    }
+
+    fn write_registers(&mut self, args: &str) -> String {
+        let Some(bytes) = unhex(args) else {
+            return "E00".to_string();
+        };
+        let mut rest = &bytes[..];
+        for n in 0..REGISTERS {
+            let size = register_size(n);
+            if rest.len() < size {
+                return "E00".to_string();
+            }
+            self.set_register(n, &rest[..size]);
+            rest = &rest[size..];
+        }
+        "OK".to_string()
+    }
+
+    fn write_register(&mut self, args: &str) -> String {
+        let Some((n, value)) = args.split_once('=') else {
+            return "E00".to_string();
+        };
+        match (usize::from_str_radix(n, 16), unhex(value)) {
+            (Ok(n), Some(bytes)) if n < REGISTERS => {
+                self.set_register(n, &bytes);
+                "OK".to_string()
+            }
+            _ => "E00".to_string(),
+        }
+    }
+
+    fn read_memory(&self, args: &str) -> String {
+        let Some((addr, len)) = address_and_length(args) else {
+            return "E00".to_string();
+        };
+        let mut out = String::new();
+        for a in addr..addr.saturating_add(len) {
+            match self.peek(a) {
+                Some(b) => out.push_str(&format!("{b:02x}")),
+                // A partial read is fine; nothing readable is an error.
+                None if out.is_empty() => return "E14".to_string(),
+                None => break,
+            }
+        }
+        out
+    }
+
+    fn write_memory(&mut self, args: &str) -> String {
+        let Some((range, data)) = args.split_once(':') else {
+            return "E00".to_string();
+        };
+        let (Some((addr, len)), Some(bytes)) = (address_and_length(range), unhex(data)) else {
+            return "E00".to_string();
+        };
+        self.store(addr, len, &bytes)
+    }
+
+    /// `X`: like `M`, with the data in binary, `}`-escaped.
+    fn write_binary(&mut self, args: &[u8]) -> String {
+        let Some(colon) = args.iter().position(|&b| b == b':') else {
+            return "E00".to_string();
+        };
+        let (range, data) = (&args[..colon], &args[colon + 1..]);
+        let range = std::str::from_utf8(range).ok().and_then(address_and_length);
+        let (Some((addr, len)), Some(bytes)) = (range, unescape(data)) else {
+            return "E00".to_string();
+        };
+        self.store(addr, len, &bytes)
+    }
+
+    /// Writes `len` bytes at `addr`, all or none.
+    fn store(&mut self, addr: u32, len: u32, bytes: &[u8]) -> String {
+        if bytes.len() != len as usize {
+            return "E00".to_string();
+        }
+        if (addr..addr.saturating_add(len)).any(|a| self.peek(a).is_none()) {
+            return "E14".to_string();
+        }
+        for (a, &b) in (addr..).zip(bytes) {
+            self.poke(a, b);
+        }
+        "OK".to_string()
+    }
+
+    fn peek(&self, addr: u32) -> Option<u8> {
+        let bus = self.dbg.machine().bus();
+        let ram = bus.ram();
+        match location(addr)? {
+            Memory::Prog(a) => Some(bus.prog_read(a)),
+            Memory::Ram(l) => Some(match l.cell {
+                Cell::Char(ch) => ram.character(l.bank, l.chip, l.reg, ch),
+                Cell::Status(n) => ram.status(l.bank, l.chip, l.reg, n),
+            }),
+        }
+    }
+
+    fn poke(&mut self, addr: u32, value: u8) {
+        let bus = self.dbg.machine_mut().bus_mut();
+        match location(addr) {
+            Some(Memory::Prog(a)) => bus.set_prog_byte(a, value),
+            Some(Memory::Ram(l)) => match l.cell {
+                Cell::Char(ch) => bus
+                    .ram_mut()
+                    .set_character(l.bank, l.chip, l.reg, ch, value),
+                Cell::Status(n) => bus.ram_mut().set_status(l.bank, l.chip, l.reg, n, value),
+            },
+            None => {}
+        }
+    }
+
+    fn breakpoint(&mut self, insert: bool, args: &str) -> String {
+        let mut fields = args.splitn(3, ',');
+        let kind = fields.next().and_then(|k| k.parse::<u8>().ok());
+        let addr = fields.next().and_then(|a| u32::from_str_radix(a, 16).ok());
+        let len = fields.next().and_then(|l| u32::from_str_radix(l, 16).ok());
+        let (Some(kind), Some(addr), Some(len)) = (kind, addr, len) else {
+            return "E00".to_string();
+        };
+        match (kind, insert) {
+            (0 | 1, true) => {
+                let Some(Memory::Prog(pc)) = location(addr) else {
+                    return "E14".to_string();
+                };
+                let id = self.dbg.break_at(pc);
+                if let Some(old) = self.breakpoints.insert((kind, addr), id) {
+                    self.dbg.remove(old);
+                }
+            }
+            (0 | 1, false) => {
+                if let Some(id) = self.breakpoints.remove(&(kind, addr)) {
+                    self.dbg.remove(id);
+                }
+            }
+            (2..=4, true) => {
+                let watch = match kind {
+                    2 => Watch::Write,
+                    3 => Watch::Read,
+                    _ => Watch::Any,
+                };
+                let mut ids = Vec::new();
+                for a in addr..addr.saturating_add(len) {
+                    match location(a) {
+                        Some(Memory::Ram(l)) => ids.push(self.dbg.watch_ram(l, watch)),
+                        _ => {
+                            for id in ids {
+                                self.dbg.remove(id);
+                            }
+                            return "E14".to_string();
+                        }
+                    }
+                }
+                self.watchpoints.insert((kind, addr, len), ids);
+            }
+            (2..=4, false) => {
+                for id in self
+                    .watchpoints
+                    .remove(&(kind, addr, len))
+                    .unwrap_or_default()
+                {
+                    self.dbg.remove(id);
+                }
+            }
+            _ => return String::new(),
+        }
+        "OK".to_string()
+    }
+
+    fn resume(&mut self, step: bool) -> String {
+        let reason = if step {
+            self.dbg.step()
+        } else {
+            loop {
+                match self.dbg.run(CHUNK) {
+                    StopReason::StepLimit => {}
+                    reason => break reason,
+                }
+                // On a socket error, stop too; reading the next packet reports it.
+                if self.interrupted().unwrap_or(true) {
+                    return "S02".to_string();
+                }
+            }
+        };
+        match reason {
+            StopReason::Ram { access, .. } => {
+                let kind = match (access.direction, self.watch_kind(&reason)) {
+                    (_, Some(Watch::Any)) => "awatch",
+                    (Direction::Read, _) => "rwatch",
+                    (Direction::Write, _) => "watch",
+                };
+                format!("T05{kind}:{:x};", ram_address(access.location))
+            }
+            _ => "S05".to_string(),
+        }
+    }
+
+    /// The kind of watchpoint that stopped the machine.
+    fn watch_kind(&self, reason: &StopReason) -> Option<Watch> {
+        let StopReason::Ram { id, .. } = reason else {
+            return None;
+        };
+        self.watchpoints
+            .iter()
+            .find(|(_, ids)| ids.contains(id))
+            .map(|((kind, ..), _)| match kind {
+                2 => Watch::Write,
+                3 => Watch::Read,
+                _ => Watch::Any,
+            })
+    }
+}
+
+enum Memory {
+    Prog(u16),
+    Ram(RamLocation),
+}
+
+fn location(addr: u32) -> Option<Memory> {
+    let (bank, chip, reg);
+    let cell = match addr {
+        PROG_BASE..0x1000 => return Some(Memory::Prog((addr - PROG_BASE) as u16)),
+        RAM_BASE..0x1_0800 => {
+            let a = addr - RAM_BASE;
+            (bank, chip, reg) = ((a >> 8) as u8, (a >> 6) as u8 & 0x3, (a >> 4) as u8 & 0x3);
+            Cell::Char(a as u8 & 0xF)
+        }
+        STATUS_BASE..0x2_0200 => {
+            let a = addr - STATUS_BASE;
+            (bank, chip, reg) = ((a >> 6) as u8, (a >> 4) as u8 & 0x3, (a >> 2) as u8 & 0x3);
+            Cell::Status(a as u8 & 0x3)
+        }
+        _ => return None,
+    };
+    Some(Memory::Ram(RamLocation {
+        bank,
+        chip,
+        reg,
+        cell,
+    }))
+}
+
+/// Where GDB sees a RAM nibble.
+pub fn ram_address(l: RamLocation) -> u32 {
+    let (bank, chip, reg) = (l.bank as u32, l.chip as u32, l.reg as u32);
+    match l.cell {
+        Cell::Char(ch) => RAM_BASE | bank << 8 | chip << 6 | reg << 4 | ch as u32,
+        Cell::Status(n) => STATUS_BASE | bank << 6 | chip << 4 | reg << 2 | n as u32,
+    }
+}
+
+fn register_size(n: usize) -> usize {
+    if (PC..SP).contains(&n) { 2 } else { 1 }
+}
+
+fn address_and_length(args: &str) -> Option<(u32, u32)> {
+    let (addr, len) = args.split_once(',')?;
+    Some((
+        u32::from_str_radix(addr, 16).ok()?,
+        u32::from_str_radix(len, 16).ok()?,
+    ))
+}
+
+fn hex16(value: u16) -> String {
+    value
+        .to_le_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Binary packet data with its `}` escapes undone; `None` if one is cut short.
+fn unescape(data: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut bytes = data.iter();
+    while let Some(&b) = bytes.next() {
+        out.push(match b {
+            b'}' => bytes.next()? ^ 0x20,
+            _ => b,
+        });
+    }
+    Some(out)
+}
+
+fn sum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |s, &b| s.wrapping_add(b))
+}
@@ -0,0 +1,557 @@
+//! Breakpoints and watchpoints over a [`Machine`].
+//!
+//! A [`Debugger`] owns a machine and runs it one instruction at a time,
+//! looking at each instruction before it executes:
+//!
+//! - PC breakpoints and instruction breakpoints (e.g. any `DCL`) stop before
+//!   the instruction runs, with the PC on it;
+//! - RAM watchpoints (`RDM`, `WRM`, `RD0`–`RD3`, `WR0`–`WR3`, `ADM`, `SBM`) and
+//!   port watchpoints (`WRR`, `WMP`, `RDR`) stop right after the access, with
+//!   the value read or written.
+//!
+//! Every breakpoint counts its hits and can ignore the first few, and PC
+//! breakpoints can carry a condition on the CPU. A device requesting a stop
+//! (see [`DeviceContext`](crate::dev::DeviceContext)) stops the debugger too.
+//!
+//! # Example
+//!
+//! ```
+//! use intel_4004::bus::simple::SimpleBus;
+//! use intel_4004::chips::{DataRam4002, Rom4001};
+//! use intel_4004::debugger::{Debugger, StopReason};
+//! use intel_4004::machine::Machine;
+//!
+//! // 000: IAC; JUN 000H
+//! let rom = Rom4001::from_bytes(&[0xF2, 0x40, 0x00]);
+//! let mut dbg = Debugger::new(Machine::new(SimpleBus::new(rom, DataRam4002::default())));
+//! let id = dbg.break_if(0x000, |cpu| cpu.acc() == 5);
+//! assert_eq!(dbg.run(1000), StopReason::Breakpoint { id, pc: 0x000 });
+//! assert_eq!(dbg.hits(id), 1);
+//! ```
+
+pub mod gdb;
+pub mod repl;
+
+use std::fmt;
+
+use crate::bus::Inspect;
+use crate::chips::Cpu4004;
+use crate::dev::PortId;
+use crate::isa::Instruction;
+use crate::machine::Machine;
+
+/// Identifies a breakpoint or watchpoint of a [`Debugger`].
+pub type BreakId = usize;
+
+/// Whether an access read or wrote.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Read,
+    Write,
+}
+
+/// Which accesses a watchpoint stops on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Watch {
+    Read,
+    Write,
+    Any,
+}
+
+impl Watch {
+    fn matches(self, dir: Direction) -> bool {
+        matches!(
+            (self, dir),
+            (Watch::Any, _) | (Watch::Read, Direction::Read) | (Watch::Write, Direction::Write)
+        )
+    }
+}
+
+/// A main memory character or a status character of a 4002 register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cell {
+    Char(u8),
+    Status(u8),
+}
+
+/// One nibble of data RAM.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RamLocation {
+    pub bank: u8,
+    pub chip: u8,
+    pub reg: u8,
+    pub cell: Cell,
+}
+
+impl fmt::Display for RamLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bank {} chip {} reg {} ", self.bank, self.chip, self.reg)?;
+        match self.cell {
+            Cell::Char(n) => write!(f, "char {n:X}"),
+            Cell::Status(n) => write!(f, "status {n}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RamAccess {
+    pub location: RamLocation,
+    pub direction: Direction,
+    pub value: u8,
+    /// Address of the accessing instruction.
+    pub pc: u16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PortAccess {
+    /// `WRR` and `RDR` reach [`PortId::Rom`], or the 4289 port selected by
+    /// `SRC`; `WMP` reaches [`PortId::Ram`].
+    pub port: PortId,
+    pub direction: Direction,
+    pub value: u8,
+    /// Address of the accessing instruction.
+    pub pc: u16,
+}
+
+/// Why [`Debugger::step`] or [`Debugger::run`] returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// About to execute the instruction at a PC breakpoint.
+    Breakpoint { id: BreakId, pc: u16 },
+    /// About to execute an instruction matched by an instruction breakpoint.
+    Instruction {
+        id: BreakId,
+        pc: u16,
+        instr: Instruction,
+    },
+    /// Just after a watched RAM access.
+    Ram { id: BreakId, access: RamAccess },
+    /// Just after a watched port access.
+    Port { id: BreakId, access: PortAccess },
+    /// A device asked the machine to stop.
+    Device,
+    /// The single step is done.
+    Step,
+    /// [`Debugger::run`] executed all the instructions it was allowed.
+    StepLimit,
+}
+
+type Condition = Box<dyn FnMut(&Cpu4004) -> bool>;
+type Matcher = Box<dyn FnMut(&Instruction) -> bool>;
+
+enum Kind {
+    Pc(u16, Option<Condition>),
+    Instruction(Matcher),
+    Ram {
+        bank: u8,
+        chip: u8,
+        reg: u8,
+        cell: Option<Cell>,
+        watch: Watch,
+    },
+    Port(PortId, Watch),
+}
+
+struct Breakpoint {
+    id: BreakId,
+    kind: Kind,
+    what: String,
+    enabled: bool,
+    hits: u64,
+    ignore: u64,
+}
+
+impl Breakpoint {
+    /// Counts a hit, and tells whether it stops the machine.
+    fn hit(&mut self) -> bool {
+        self.hits += 1;
+        self.hits > self.ignore
+    }
+}
+
+/// A breakpoint as listed by [`Debugger::breakpoints`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BreakInfo {
+    pub id: BreakId,
+    /// Description, e.g. `PC 01AH` or `watch write bank 0 chip 1 reg 2 char 7`.
+    pub what: String,
+    pub enabled: bool,
+    pub hits: u64,
+    /// Hits that do not stop the machine, before the next one does.
+    pub ignore: u64,
+}
+
+enum Access {
+    Ram(RamAccess),
+    Port(PortAccess),
+}
+
+pub struct Debugger<B: Inspect> {
+    machine: Machine<B>,
+    breakpoints: Vec<Breakpoint>,
+    next_id: BreakId,
+    /// PC and cycle count of the last stop before an instruction, which
+    /// running again must not stop on.
+    stopped_before: Option<(u16, u64)>,
+    /// `JMS` executed under the debugger and not yet returned from by `BBL`.
+    depth: usize,
+}
+
+impl<B: Inspect> Debugger<B> {
+    pub fn new(machine: Machine<B>) -> Self {
+        Self {
+            machine,
+            breakpoints: Vec::new(),
+            next_id: 1,
+            stopped_before: None,
+            depth: 0,
+        }
+    }
+
+    pub fn machine(&self) -> &Machine<B> {
+        &self.machine
+    }
+
+    pub fn machine_mut(&mut self) -> &mut Machine<B> {
+        &mut self.machine
+    }
+
+    pub fn into_machine(self) -> Machine<B> {
+        self.machine
+    }
+
+    /// The instruction at PC, about to execute.
+    pub fn current_instruction(&self) -> Instruction {
+        let bus = self.machine.bus();
+        let pc = self.machine.cpu().pc();
+        Instruction::decode(bus.prog_read(pc), bus.prog_read((pc + 1) & 0x0FFF))
+    }
+
+    /// Stops before executing the instruction at `addr`.
+    pub fn break_at(&mut self, addr: u16) -> BreakId {
+        let addr = addr & 0x0FFF;
+        self.add(Kind::Pc(addr, None), format!("PC {addr:03X}H"))
+    }
+
+    /// Stops before executing the instruction at `addr` if `condition` holds.
+    /// Hits are only counted when it does.
+    pub fn break_if(
+        &mut self,
+        addr: u16,
+        condition: impl FnMut(&Cpu4004) -> bool + 'static,
+    ) -> BreakId {
+        let addr = addr & 0x0FFF;
+        let kind = Kind::Pc(addr, Some(Box::new(condition)));
+        self.add(kind, format!("PC {addr:03X}H if condition"))
+    }
+
+    /// Stops before executing any instruction `matches` accepts.
+    pub fn break_on(&mut self, matches: impl FnMut(&Instruction) -> bool + 'static) -> BreakId {
+        self.add(
+            Kind::Instruction(Box::new(matches)),
+            "instruction".to_string(),
+        )
+    }
+
+    /// Stops before executing any instruction with this mnemonic, e.g. `DCL`.
+    pub fn break_on_mnemonic(&mut self, mnemonic: &str) -> BreakId {
+        let wanted = mnemonic.to_ascii_uppercase();
+        let what = format!("instruction {wanted}");
+        let matches = move |instr: &Instruction| {
+            instr.to_string().split_whitespace().next() == Some(wanted.as_str())
+        };
+        self.add(Kind::Instruction(Box::new(matches)), what)
+    }
+
+    /// Stops after `watch` accesses to one RAM nibble.
+    pub fn watch_ram(&mut self, location: RamLocation, watch: Watch) -> BreakId {
+        let RamLocation {
+            bank,
+            chip,
+            reg,
+            cell,
+        } = location;
+        let kind = Kind::Ram {
+            bank,
+            chip,
+            reg,
+            cell: Some(cell),
+            watch,
+        };
+        self.add(kind, format!("watch {} {location}", watch_name(watch)))
+    }
+
+    /// Stops after `watch` accesses to any character or status character of
+    /// a RAM register.
+    pub fn watch_register(&mut self, bank: u8, chip: u8, reg: u8, watch: Watch) -> BreakId {
+        let kind = Kind::Ram {
+            bank,
+            chip,
+            reg,
+            cell: None,
+            watch,
+        };
+        let what = format!(
+            "watch {} bank {bank} chip {chip} reg {reg}",
+            watch_name(watch)
+        );
+        self.add(kind, what)
+    }
+
+    /// Stops after `watch` accesses to a port.
+    pub fn watch_port(&mut self, port: PortId, watch: Watch) -> BreakId {
+        let what = format!("watch {} {port} port", watch_name(watch));
+        self.add(Kind::Port(port, watch), what)
+    }
+
+    /// Removes a breakpoint; false if there was none with this id.
+    pub fn remove(&mut self, id: BreakId) -> bool {
+        let len = self.breakpoints.len();
+        self.breakpoints.retain(|b| b.id != id);
+        self.breakpoints.len() != len
+    }
+
+    pub fn clear(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Enables or disables a breakpoint; false if there was none with this id.
+    pub fn set_enabled(&mut self, id: BreakId, enabled: bool) -> bool {
+        self.find(id).map(|b| b.enabled = enabled).is_some()
+    }
+
+    /// Lets the next `count` hits pass; false if there was none with this id.
+    pub fn set_ignore_count(&mut self, id: BreakId, count: u64) -> bool {
+        self.find(id).map(|b| b.ignore = b.hits + count).is_some()
+    }
+
+    /// Times the breakpoint was hit, stopping or not.
+    pub fn hits(&self, id: BreakId) -> u64 {
+        self.breakpoints
+            .iter()
+            .find(|b| b.id == id)
+            .map_or(0, |b| b.hits)
+    }
+
+    pub fn breakpoints(&self) -> Vec<BreakInfo> {
+        self.breakpoints
+            .iter()
+            .map(|b| BreakInfo {
+                id: b.id,
+                what: b.what.clone(),
+                enabled: b.enabled,
+                hits: b.hits,
+                ignore: b.ignore.saturating_sub(b.hits),
+            })
+            .collect()
+    }
+
+    /// Executes one instruction, ignoring breakpoints on it.
+    pub fn step(&mut self) -> StopReason {
+        self.execute().unwrap_or(StopReason::Step)
+    }
+
+    /// Executes up to `max_steps` instructions until something stops the
+    /// machine. Running again after a breakpoint executes the instruction it
+    /// stopped on.
+    pub fn run(&mut self, max_steps: usize) -> StopReason {
+        self.resume(max_steps, None)
+    }
+
+    /// Like [`Debugger::step`], but runs a whole subroutine called by `JMS`,
+    /// stopping when it returns unless something else stops it first.
+    pub fn next(&mut self, max_steps: usize) -> StopReason {
+        match self.current_instruction() {
+            Instruction::Jms { .. } => self.resume(max_steps, Some(self.depth)),
+            _ => self.step(),
+        }
+    }
+
+    /// Runs until the current subroutine returns, unless something else
+    /// stops it first. `None` when no `JMS` made under the debugger is active.
+    pub fn finish(&mut self, max_steps: usize) -> Option<StopReason> {
+        let depth = self.depth.checked_sub(1)?;
+        Some(self.resume(max_steps, Some(depth)))
+    }
+
+    /// Runs like [`Debugger::run`], also stopping with [`StopReason::Step`]
+    /// once the call depth is back down to `return_to`.
+    fn resume(&mut self, max_steps: usize, return_to: Option<usize>) -> StopReason {
+        for _ in 0..max_steps {
+            let here = (self.machine.cpu().pc(), self.machine.cycles());
+            if self.stopped_before != Some(here)
+                && let Some(reason) = self.check_before()
+            {
+                self.stopped_before = Some(here);
+                return reason;
+            }
+            if let Some(reason) = self.execute() {
+                return reason;
+            }
+            if return_to.is_some_and(|depth| self.depth <= depth) {
+                return StopReason::Step;
+            }
+        }
+        StopReason::StepLimit
+    }
+
+    fn add(&mut self, kind: Kind, what: String) -> BreakId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.breakpoints.push(Breakpoint {
+            id,
+            kind,
+            what,
+            enabled: true,
+            hits: 0,
+            ignore: 0,
+        });
+        id
+    }
+
+    fn find(&mut self, id: BreakId) -> Option<&mut Breakpoint> {
+        self.breakpoints.iter_mut().find(|b| b.id == id)
+    }
+
+    /// PC and instruction breakpoints on the instruction about to execute.
+    fn check_before(&mut self) -> Option<StopReason> {
+        let instr = self.current_instruction();
+        let cpu = self.machine.cpu();
+        let pc = cpu.pc();
+        let mut reason = None;
+        for b in self.breakpoints.iter_mut().filter(|b| b.enabled) {
+            let stop = match &mut b.kind {
+                Kind::Pc(addr, condition) => {
+                    *addr == pc && condition.as_mut().is_none_or(|c| c(cpu)) && b.hit()
+                }
+                Kind::Instruction(matches) => matches(&instr) && b.hit(),
+                _ => false,
+            };
+            if stop && reason.is_none() {
+                reason = Some(match b.kind {
+                    Kind::Pc(..) => StopReason::Breakpoint { id: b.id, pc },
+                    _ => StopReason::Instruction {
+                        id: b.id,
+                        pc,
+                        instr,
+                    },
+                });
+            }
+        }
+        reason
+    }
+
+    /// Executes one instruction and checks the watchpoints on its access.
+    fn execute(&mut self) -> Option<StopReason> {
+        let instr = self.current_instruction();
+        let mut access = self.access(instr);
+        match instr {
+            Instruction::Jms { .. } => self.depth += 1,
+            Instruction::Bbl { .. } => self.depth = self.depth.saturating_sub(1),
+            _ => {}
+        }
+        let device = self.machine.run_steps(1);
+        if let Some(Access::Port(port)) = &mut access
+            && port.direction == Direction::Read
+        {
+            port.value = self.machine.cpu().acc();
+        }
+
+        let mut reason = None;
+        for b in self.breakpoints.iter_mut().filter(|b| b.enabled) {
+            let stop = match (&b.kind, &access) {
+                (
+                    &Kind::Ram {
+                        bank,
+                        chip,
+                        reg,
+                        cell,
+                        watch,
+                    },
+                    Some(Access::Ram(a)),
+                ) => {
+                    let l = a.location;
+                    (l.bank, l.chip, l.reg) == (bank, chip, reg)
+                        && cell.is_none_or(|c| c == l.cell)
+                        && watch.matches(a.direction)
+                        && b.hit()
+                }
+                (&Kind::Port(port, watch), Some(Access::Port(a))) => {
+                    port == a.port && watch.matches(a.direction) && b.hit()
+                }
+                _ => false,
+            };
+            if stop && reason.is_none() {
+                reason = access.as_ref().map(|a| match *a {
+                    Access::Ram(access) => StopReason::Ram { id: b.id, access },
+                    Access::Port(access) => StopReason::Port { id: b.id, access },
+                });
+            }
+        }
+        reason.or(device.then_some(StopReason::Device))
+    }
+
+    /// The RAM or port access `instr` is about to make. Port reads get their
+    /// value once the instruction has run.
+    fn access(&self, instr: Instruction) -> Option<Access> {
+        let cpu = self.machine.cpu();
+        let (pc, acc) = (cpu.pc(), cpu.acc());
+        let ram = self.machine.bus().ram();
+        let (bank, addr8) = (ram.bank(), ram.address());
+        let (chip, reg, ch) = (addr8 >> 6, (addr8 >> 4) & 0x3, addr8 & 0xF);
+
+        let (cell, direction) = match instr {
+            Instruction::Wrm => (Cell::Char(ch), Direction::Write),
+            Instruction::Rdm | Instruction::Adm | Instruction::Sbm => {
+                (Cell::Char(ch), Direction::Read)
+            }
+            Instruction::Wr0 => (Cell::Status(0), Direction::Write),
+            Instruction::Wr1 => (Cell::Status(1), Direction::Write),
+            Instruction::Wr2 => (Cell::Status(2), Direction::Write),
+            Instruction::Wr3 => (Cell::Status(3), Direction::Write),
+            Instruction::Rd0 => (Cell::Status(0), Direction::Read),
+            Instruction::Rd1 => (Cell::Status(1), Direction::Read),
+            Instruction::Rd2 => (Cell::Status(2), Direction::Read),
+            Instruction::Rd3 => (Cell::Status(3), Direction::Read),
+            Instruction::Wrr | Instruction::Wmp | Instruction::Rdr => {
+                let rom = self.machine.bus().rom_port_id();
+                let (port, direction) = match instr {
+                    Instruction::Wrr => (rom, Direction::Write),
+                    Instruction::Wmp => (PortId::Ram, Direction::Write),
+                    _ => (rom, Direction::Read),
+                };
+                return Some(Access::Port(PortAccess {
+                    port,
+                    direction,
+                    value: acc,
+                    pc,
+                }));
+            }
+            _ => return None,
+        };
+        let value = match (direction, cell) {
+            (Direction::Write, _) => acc,
+            (Direction::Read, Cell::Char(n)) => ram.character(bank, chip, reg, n),
+            (Direction::Read, Cell::Status(n)) => ram.status(bank, chip, reg, n),
+        };
+        Some(Access::Ram(RamAccess {
+            location: RamLocation {
+                bank,
+                chip,
+                reg,
+                cell,
+            },
+            direction,
+            value,
+            pc,
+        }))
+    }
+}
+
+fn watch_name(watch: Watch) -> &'static str {
+    match watch {
+        Watch::Read => "read",
+        Watch::Write => "write",
+        Watch::Any => "access",
+    }
+}
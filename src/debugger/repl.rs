@@ -0,0 +1,531 @@
+//! Command-line front end for the [`Debugger`].
+//!
+//! [`run`] reads commands line by line and prints to any writer, so the
+//! `debug` subcommand hands it stdin and stdout and tests hand it strings.
+//! An empty line repeats the previous command. Addresses and values are hex,
+//! with an optional `H` suffix or `0x` prefix.
+//!
+//! ```text
+//! s, step [n]              execute n instructions (1)
+//! n, next                  step, running over a JMS
+//! finish                   run until the current subroutine returns
+//! c, continue [n]          run until a breakpoint, at most n instructions
+//! b, break ADDR [if COND]  breakpoint, COND like acc==5, r3!=0, cy==1
+//! catch MNEMONIC           break before any instruction, e.g. catch DCL
+//! watch [read|write] LOC   watchpoint on RAM (b:c:r, b:c:r:ch, b:c:r:sN)
+//!                          or on a port (rom, ram, io0–ioF)
+//! info                     list breakpoints
+//! delete [ID]              delete one breakpoint, or all
+//! enable ID, disable ID
+//! ignore ID N              let the next N hits pass
+//! regs                     registers, stack and cycle count
+//! set acc|cy|pc|rN VALUE   edit a register
+//! set LOC VALUE            edit a RAM character (b:c:r:ch) or status (b:c:r:sN)
+//! ram [BANK]               hex dump of a bank (the DCL one)
+//! disas [ADDR] [n]         disassemble n lines around ADDR (PC)
+//! q, quit
+//! ```
+
+use std::io::{self, BufRead, Write};
+
+use crate::bus::Inspect;
+use crate::chips::Cpu4004;
+use crate::debugger::{BreakId, Cell, Debugger, RamLocation, StopReason, Watch};
+use crate::dev::PortId;
+use crate::disasm::disassemble;
+
+/// Instructions `continue`, `next` and `finish` run before giving up.
+pub const RUN_LIMIT: usize = 10_000_000;
+
+/// The last program memory address.
+const ADDR_MAX: u16 = 0xFFF;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Acc,
+    Cy,
+    Pc,
+    Reg(u8),
+}
+
+impl Field {
+    fn get(self, cpu: &Cpu4004) -> u16 {
+        match self {
+            Field::Acc => cpu.acc() as u16,
+            Field::Cy => cpu.cy() as u16,
+            Field::Pc => cpu.pc(),
+            Field::Reg(n) => cpu.reg(n) as u16,
+        }
+    }
+
+    /// The largest value the field holds.
+    fn max(self) -> u16 {
+        match self {
+            Field::Cy => 1,
+            Field::Pc => ADDR_MAX,
+            Field::Acc | Field::Reg(_) => 0xF,
+        }
+    }
+}
+
+enum Target {
+    Ram {
+        bank: u8,
+        chip: u8,
+        reg: u8,
+        cell: Option<Cell>,
+    },
+    Port(PortId),
+}
+
+enum Command {
+    Step(usize),
+    Next,
+    Finish,
+    Continue(usize),
+    Break(u16, Option<(Field, bool, u16)>),
+    Catch(String),
+    Watch(Watch, Target),
+    Info,
+    Delete(Option<BreakId>),
+    Enable(BreakId, bool),
+    Ignore(BreakId, u64),
+    Regs,
+    Set(Field, u16),
+    SetRam(RamLocation, u8),
+    Ram(Option<u8>),
+    Disas(Option<u16>, usize),
+    Help,
+    Quit,
+}
+
+/// Reads and executes commands from `input` until it ends or `quit`.
+pub fn run<B: Inspect>(
+    dbg: &mut Debugger<B>,
+    input: impl BufRead,
+    mut out: impl Write,
+) -> io::Result<()> {
+    show_pc(dbg, &mut out)?;
+    let mut last = String::new();
+    write!(out, "(4004) ")?;
+    out.flush()?;
+    for line in input.lines() {
+        let mut line = line?;
+        if line.trim().is_empty() {
+            line = last.clone();
+        }
+        if !line.trim().is_empty() {
+            match parse(&line) {
+                Ok(Command::Quit) => return Ok(()),
+                Ok(cmd) => execute(dbg, cmd, &mut out)?,
+                Err(msg) => writeln!(out, "{msg}")?,
+            }
+        }
+        last = line;
+        write!(out, "(4004) ")?;
+        out.flush()?;
+    }
+    writeln!(out)
+}
+
+fn parse(line: &str) -> Result<Command, String> {
+    let mut words = line.split_whitespace();
+    let cmd = words.next().unwrap_or_default();
+    let args: Vec<&str> = words.collect();
+    let arg = |i: usize| args.get(i).copied();
+    let count = |i: usize, default: usize| arg(i).map_or(Ok(default), |s| number(s, 10));
+    let id = |i: usize| match arg(i) {
+        Some(s) => s
+            .parse::<BreakId>()
+            .map_err(|_| format!("bad breakpoint id `{s}`")),
+        None => Err("missing breakpoint id".to_string()),
+    };
+
+    Ok(match cmd {
+        "s" | "step" => Command::Step(count(0, 1)?),
+        "n" | "next" => Command::Next,
+        "finish" => Command::Finish,
+        "c" | "continue" => Command::Continue(count(0, RUN_LIMIT)?),
+        "b" | "break" => {
+            let addr = hex(arg(0).ok_or("usage: break ADDR [if COND]")?, ADDR_MAX)?;
+            let cond = match arg(1) {
+                None => None,
+                Some("if") => Some(condition(&args[2..].concat())?),
+                Some(_) => return Err("usage: break ADDR [if COND]".into()),
+            };
+            Command::Break(addr, cond)
+        }
+        "catch" => Command::Catch(arg(0).ok_or("usage: catch MNEMONIC")?.to_string()),
+        "watch" => {
+            let (watch, loc) = match (arg(0), arg(1)) {
+                (Some("read"), Some(loc)) => (Watch::Read, loc),
+                (Some("write"), Some(loc)) => (Watch::Write, loc),
+                (Some(loc), None) => (Watch::Any, loc),
+                _ => return Err("usage: watch [read|write] LOC".into()),
+            };
+            let target = match loc {
+                "rom" => Target::Port(PortId::Rom),
+                "ram" => Target::Port(PortId::Ram),
+                loc if loc.starts_with("io") => Target::Port(io_port(loc)?),
+                loc => {
+                    let (bank, chip, reg, cell) = location(loc)?;
+                    Target::Ram {
+                        bank,
+                        chip,
+                        reg,
+                        cell,
+                    }
+                }
+            };
+            Command::Watch(watch, target)
+        }
+        "info" => Command::Info,
+        "delete" => Command::Delete(arg(0).map(|_| id(0)).transpose()?),
+        "enable" => Command::Enable(id(0)?, true),
+        "disable" => Command::Enable(id(0)?, false),
+        "ignore" => Command::Ignore(id(0)?, count(1, 0)? as u64),
+        "regs" => Command::Regs,
+        "set" => {
+            let (target, value) = match (arg(0), arg(1)) {
+                (Some(t), Some(v)) => (t, v),
+                _ => return Err("usage: set acc|cy|pc|rN|LOC VALUE".into()),
+            };
+            match field(target) {
+                Some(f) => Command::Set(f, hex(value, f.max())?),
+                None => match location(target)? {
+                    (bank, chip, reg, Some(cell)) => Command::SetRam(
+                        RamLocation {
+                            bank,
+                            chip,
+                            reg,
+                            cell,
+                        },
+                        hex(value, 0xF)? as u8,
+                    ),
+                    _ => return Err("set needs a character (b:c:r:ch) or status (b:c:r:sN)".into()),
+                },
+            }
+        }
+        "ram" => Command::Ram(arg(0).map(|s| hex(s, 7)).transpose()?.map(|b| b as u8)),
+        "disas" => Command::Disas(arg(0).map(|s| hex(s, ADDR_MAX)).transpose()?, count(1, 10)?),
+        "h" | "help" => Command::Help,
+        "q" | "quit" => Command::Quit,
+        _ => return Err(format!("unknown command `{cmd}`, try `help`")),
+    })
+}
+
+fn execute<B: Inspect>(
+    dbg: &mut Debugger<B>,
+    cmd: Command,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    match cmd {
+        Command::Step(n) => {
+            for _ in 0..n {
+                let reason = dbg.step();
+                if reason != StopReason::Step {
+                    report(dbg, reason, out)?;
+                    break;
+                }
+            }
+            show_pc(dbg, out)?;
+        }
+        Command::Next => stopped(dbg, |d| d.next(RUN_LIMIT), out)?,
+        Command::Finish => match dbg.finish(RUN_LIMIT) {
+            Some(reason) => {
+                report(dbg, reason, out)?;
+                show_pc(dbg, out)?;
+            }
+            None => writeln!(out, "No subroutine to finish")?,
+        },
+        Command::Continue(n) => stopped(dbg, |d| d.run(n), out)?,
+        Command::Break(addr, cond) => {
+            let id = match cond {
+                None => dbg.break_at(addr),
+                Some((f, eq, value)) => dbg.break_if(addr, move |cpu| (f.get(cpu) == value) == eq),
+            };
+            writeln!(out, "Breakpoint {id} at {:03X}H", addr & 0x0FFF)?;
+        }
+        Command::Catch(mnemonic) => {
+            let id = dbg.break_on_mnemonic(&mnemonic);
+            writeln!(out, "Catchpoint {id}: {}", mnemonic.to_ascii_uppercase())?;
+        }
+        Command::Watch(watch, target) => {
+            let id = match target {
+                Target::Ram {
+                    bank,
+                    chip,
+                    reg,
+                    cell: Some(cell),
+                } => dbg.watch_ram(
+                    RamLocation {
+                        bank,
+                        chip,
+                        reg,
+                        cell,
+                    },
+                    watch,
+                ),
+                Target::Ram {
+                    bank, chip, reg, ..
+                } => dbg.watch_register(bank, chip, reg, watch),
+                Target::Port(port) => dbg.watch_port(port, watch),
+            };
+            writeln!(out, "Watchpoint {id}: {}", watched(dbg, id))?;
+        }
+        Command::Info => {
+            let breakpoints = dbg.breakpoints();
+            if breakpoints.is_empty() {
+                writeln!(out, "No breakpoints.")?;
+            }
+            for b in breakpoints {
+                let state = if b.enabled { "" } else { " (disabled)" };
+                write!(
+                    out,
+                    "{:>3}  {}{state}, hit {} time(s)",
+                    b.id, b.what, b.hits
+                )?;
+                if b.ignore > 0 {
+                    write!(out, ", ignoring {} more", b.ignore)?;
+                }
+                writeln!(out)?;
+            }
+        }
+        Command::Delete(None) => dbg.clear(),
+        Command::Delete(Some(id)) => known(dbg.remove(id), id, out)?,
+        Command::Enable(id, enabled) => known(dbg.set_enabled(id, enabled), id, out)?,
+        Command::Ignore(id, n) => known(dbg.set_ignore_count(id, n), id, out)?,
+        Command::Regs => regs(dbg.machine().cpu(), out)?,
+        Command::Set(field, value) => {
+            let cpu = dbg.machine_mut().cpu_mut();
+            match field {
+                Field::Acc => cpu.set_acc(value as u8),
+                Field::Cy => cpu.set_cy(value as u8),
+                Field::Pc => cpu.set_pc(value),
+                Field::Reg(n) => cpu.set_reg(n, value as u8),
+            }
+        }
+        Command::SetRam(l, value) => {
+            let ram = dbg.machine_mut().bus_mut().ram_mut();
+            match l.cell {
+                Cell::Char(ch) => ram.set_character(l.bank, l.chip, l.reg, ch, value),
+                Cell::Status(idx) => ram.set_status(l.bank, l.chip, l.reg, idx, value),
+            }
+        }
+        Command::Ram(bank) => {
+            let ram = dbg.machine().bus().ram();
+            let bank = bank.unwrap_or(ram.bank()) & 0x7;
+            writeln!(out, "bank {bank}   chars 0-F         status 0-3")?;
+            for chip in 0..4 {
+                for reg in 0..4 {
+                    let chars: String = (0..16)
+                        .map(|ch| format!("{:X}", ram.character(bank, chip, reg, ch)))
+                        .collect();
+                    let status: String = (0..4)
+                        .map(|i| format!("{:X}", ram.status(bank, chip, reg, i)))
+                        .collect();
+                    writeln!(out, "chip {chip} reg {reg}  {chars}  {status}")?;
+                }
+            }
+        }
+        Command::Disas(addr, n) => disas(dbg, addr, n, out)?,
+        Command::Help => writeln!(out, "{}", HELP.trim_end())?,
+        Command::Quit => {}
+    }
+    Ok(())
+}
+
+const HELP: &str = "\
+step [n], next, finish, continue [n]
+break ADDR [if acc==5], catch MNEMONIC, watch [read|write] b:c:r[:ch|:sN]|rom|ram|ioN
+info, delete [ID], enable ID, disable ID, ignore ID N
+regs, set acc|cy|pc|rN|b:c:r:ch|b:c:r:sN VALUE, ram [BANK], disas [ADDR] [n], quit
+";
+
+fn stopped<B: Inspect>(
+    dbg: &mut Debugger<B>,
+    run: impl FnOnce(&mut Debugger<B>) -> StopReason,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let reason = run(dbg);
+    report(dbg, reason, out)?;
+    show_pc(dbg, out)
+}
+
+fn report<B: Inspect>(
+    dbg: &Debugger<B>,
+    reason: StopReason,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    match reason {
+        StopReason::Breakpoint { id, pc } => writeln!(out, "Stopped at breakpoint {id}, {pc:03X}H"),
+        StopReason::Instruction { id, pc, instr } => {
+            writeln!(out, "Stopped at catchpoint {id}: {instr} at {pc:03X}H")
+        }
+        StopReason::Ram { id, access } => writeln!(
+            out,
+            "Stopped at watchpoint {id}: {} = {:X} at {:03X}H",
+            watched(dbg, id),
+            access.value,
+            access.pc
+        ),
+        StopReason::Port { id, access } => writeln!(
+            out,
+            "Stopped at watchpoint {id}: {} = {:X} at {:03X}H",
+            watched(dbg, id),
+            access.value,
+            access.pc
+        ),
+        StopReason::Device => writeln!(out, "Stopped by a device"),
+        StopReason::Step => Ok(()),
+        StopReason::StepLimit => writeln!(out, "Still running, stopped after the step limit"),
+    }
+}
+
+/// What a watchpoint watches, e.g. `write bank 0 chip 1 reg 2 char 7`.
+fn watched<B: Inspect>(dbg: &Debugger<B>, id: BreakId) -> String {
+    let b = dbg.breakpoints().into_iter().find(|b| b.id == id);
+    let what = b.map(|b| b.what).unwrap_or_default();
+    what.strip_prefix("watch ").unwrap_or(&what).to_string()
+}
+
+fn known(found: bool, id: BreakId, out: &mut impl Write) -> io::Result<()> {
+    if found {
+        Ok(())
+    } else {
+        writeln!(out, "No breakpoint {id}")
+    }
+}
+
+fn regs(cpu: &Cpu4004, out: &mut impl Write) -> io::Result<()> {
+    writeln!(
+        out,
+        "PC {:03X}H  ACC {:X}  CY {}  cycles {}",
+        cpu.pc(),
+        cpu.acc(),
+        cpu.cy(),
+        cpu.cycles()
+    )?;
+    for half in [0u8, 8] {
+        let regs: Vec<String> = (half..half + 8)
+            .map(|n| format!("{:X}", cpu.reg(n)))
+            .collect();
+        let name = format!("R{half}-R{}", half + 7);
+        writeln!(out, "{name:<7} {}", regs.join(" "))?;
+    }
+    let (stack, sp) = cpu.stack();
+    writeln!(
+        out,
+        "stack   {:03X}H {:03X}H {:03X}H  SP {sp}",
+        stack[0], stack[1], stack[2]
+    )
+}
+
+fn show_pc<B: Inspect>(dbg: &Debugger<B>, out: &mut impl Write) -> io::Result<()> {
+    disas(dbg, None, 1, out)
+}
+
+/// Disassembles `n` lines around `addr`, starting up to 3 lines before it.
+fn disas<B: Inspect>(
+    dbg: &Debugger<B>,
+    addr: Option<u16>,
+    n: usize,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let pc = dbg.machine().cpu().pc();
+    let addr = addr.unwrap_or(pc) & 0x0FFF;
+    let bus = dbg.machine().bus();
+    let prog: Vec<u8> = (0..4096).map(|a| bus.prog_read(a)).collect();
+    // Instructions have no fixed size: decode from a little before `addr`,
+    // or from `addr` itself if that falls out of step with it.
+    let before = if n > 1 { 6 } else { 0 };
+    let mut start = addr.saturating_sub(before) as usize;
+    let mut lines = disassemble(&prog[start..]);
+    let mut at = lines
+        .iter()
+        .position(|l| l.addr as usize + start == addr as usize);
+    if at.is_none() {
+        start = addr as usize;
+        lines = disassemble(&prog[start..]);
+        at = Some(0);
+    }
+    let first = at.unwrap_or(0).saturating_sub(3.min(n / 3));
+    for mut line in lines.into_iter().skip(first).take(n) {
+        line.addr += start as u16;
+        let mark = if line.addr == pc { "=>" } else { "  " };
+        writeln!(out, "{mark} {line}")?;
+    }
+    Ok(())
+}
+
+fn field(s: &str) -> Option<Field> {
+    match s.to_ascii_lowercase().as_str() {
+        "acc" | "a" => Some(Field::Acc),
+        "cy" | "c" => Some(Field::Cy),
+        "pc" => Some(Field::Pc),
+        r => {
+            let n: u8 = r.strip_prefix('r')?.parse().ok()?;
+            (n < 16).then_some(Field::Reg(n))
+        }
+    }
+}
+
+/// `acc==5`, `r3!=0`…
+fn condition(s: &str) -> Result<(Field, bool, u16), String> {
+    let (lhs, eq, rhs) = match (s.split_once("=="), s.split_once("!=")) {
+        (Some((l, r)), _) => (l, true, r),
+        (_, Some((l, r))) => (l, false, r),
+        _ => return Err(format!("bad condition `{s}`, e.g. acc==5")),
+    };
+    let f = field(lhs).ok_or(format!("unknown register `{lhs}`"))?;
+    Ok((f, eq, hex(rhs, f.max())?))
+}
+
+/// `bank:chip:reg`, optionally followed by `:ch` or `:sN`.
+fn location(s: &str) -> Result<(u8, u8, u8, Option<Cell>), String> {
+    let bad = || format!("bad RAM location `{s}`, e.g. 0:1:2, 0:1:2:F or 0:1:2:s3");
+    let parts: Vec<&str> = s.split(':').collect();
+    let nibble = |p: &str, max: u8| {
+        number(p, 16)
+            .ok()
+            .map(|n| n as u8)
+            .filter(|&n| n <= max)
+            .ok_or_else(bad)
+    };
+    let (bank, chip, reg) = match parts[..] {
+        [b, c, r] | [b, c, r, _] => (nibble(b, 7)?, nibble(c, 3)?, nibble(r, 3)?),
+        _ => return Err(bad()),
+    };
+    let cell = match parts.get(3) {
+        None => None,
+        Some(p) => match p.strip_prefix(['s', 'S']) {
+            Some(idx) => Some(Cell::Status(nibble(idx, 3)?)),
+            None => Some(Cell::Char(nibble(p, 15)?)),
+        },
+    };
+    Ok((bank, chip, reg, cell))
+}
+
+/// `io0` to `ioF`, a 4289 I/O port.
+fn io_port(s: &str) -> Result<PortId, String> {
+    number(&s[2..], 16)
+        .ok()
+        .filter(|&n| n <= 0xF)
+        .map(|n| PortId::Io(n as u8))
+        .ok_or_else(|| format!("bad port `{s}`, e.g. rom, ram or io3"))
+}
+
+/// A hex number no larger than `max`.
+fn hex(s: &str, max: u16) -> Result<u16, String> {
+    let digits = s.strip_prefix("0x").unwrap_or(s);
+    let digits = digits.strip_suffix(['h', 'H']).unwrap_or(digits);
+    let n = number(digits, 16)?;
+    u16::try_from(n)
+        .ok()
+        .filter(|&n| n <= max)
+        .ok_or_else(|| format!("`{s}` is out of range, at most {max:X}H"))
+}
+
+fn number(s: &str, radix: u32) -> Result<usize, String> {
+    usize::from_str_radix(s, radix).map_err(|_| format!("bad number `{s}`"))
+}
@@ -0,0 +1,193 @@
+//! Multiplexed seven-segment / VFD display.
+//!
+//! The ROM lights one digit at a time: it selects a digit, then sends what
+//! that digit shows. Each digit keeps glowing for a persistence time after it
+//! was last lit, so a display scanned at emulation speed reads as steady.
+//!
+//! # Wire protocol (write4)
+//!
+//! ```text
+//! Mode::Bcd        nibble 0   digit index
+//!                  nibble 1   BCD value (0–9, A = '-', others blank)
+//!
+//! Mode::Segments   nibble 0   digit index
+//!                  nibble 1   dp g f e   ┐
+//!                  nibble 2   d c b a    ┘ segment bits
+//! ```
+//!
+//! Digit 0 is the leftmost one.
+//!
+//! # Example
+//!
+//! ```
+//! use intel_4004::chips::DataRam4002;
+//! use intel_4004::dev::display::{Mode, SegmentDisplay};
+//! use intel_4004::dev::shared;
+//!
+//! let display = shared(SegmentDisplay::new(8, Mode::Bcd));
+//! let mut ram = DataRam4002::default();
+//! ram.attach_port(display.clone());
+//! // … run the machine …
+//! println!("{}", display.borrow().render());
+//! ```
+
+use crate::chips::Cpu4004;
+use crate::dev::IoDevice;
+
+pub const SEG_A: u8 = 1 << 0;
+pub const SEG_B: u8 = 1 << 1;
+pub const SEG_C: u8 = 1 << 2;
+pub const SEG_D: u8 = 1 << 3;
+pub const SEG_E: u8 = 1 << 4;
+pub const SEG_F: u8 = 1 << 5;
+pub const SEG_G: u8 = 1 << 6;
+pub const SEG_DP: u8 = 1 << 7;
+
+/// Segments of the digits 0–9.
+const DIGITS: [u8; 10] = [0x3F, 0x06, 0x5B, 0x4F, 0x66, 0x6D, 0x7D, 0x07, 0x7F, 0x6F];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// One BCD nibble per digit, decoded like a 7447.
+    Bcd,
+    /// Two nibbles of raw segment bits per digit.
+    Segments,
+}
+
+enum State {
+    Select,
+    Bcd { digit: usize },
+    SegHi { digit: usize },
+    SegLo { digit: usize, hi: u8 },
+}
+
+#[derive(Clone, Copy, Default)]
+struct Digit {
+    segments: u8,
+    lit_at: Option<u64>,
+}
+
+pub struct SegmentDisplay {
+    mode: Mode,
+    state: State,
+    digits: Vec<Digit>,
+    persistence: u64,
+    now: u64,
+}
+
+impl SegmentDisplay {
+    pub fn new(digits: usize, mode: Mode) -> Self {
+        Self {
+            mode,
+            state: State::Select,
+            digits: vec![Digit::default(); digits],
+            persistence: 15_000, // ~20 ms at 740 kHz
+            now: 0,
+        }
+    }
+
+    /// Sets how long a digit stays visible after it was last lit, in clock cycles.
+    pub fn with_persistence(mut self, cycles: u64) -> Self {
+        self.persistence = cycles;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.digits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    /// Segments currently visible on `digit`, 0 if it has faded out.
+    pub fn segments(&self, digit: usize) -> u8 {
+        match self.digits.get(digit) {
+            Some(Digit {
+                segments,
+                lit_at: Some(t),
+            }) if self.now.saturating_sub(*t) <= self.persistence => *segments,
+            _ => 0,
+        }
+    }
+
+    /// The visible digits as plain text, e.g. `"  12.50"`.
+    pub fn text(&self) -> String {
+        let mut s = String::new();
+        for digit in 0..self.digits.len() {
+            let segments = self.segments(digit);
+            s.push(match segments & !SEG_DP {
+                0 => ' ',
+                SEG_G => '-',
+                x => DIGITS
+                    .iter()
+                    .position(|&d| d == x)
+                    .map_or('?', |n| (b'0' + n as u8) as char),
+            });
+            if segments & SEG_DP != 0 {
+                s.push('.');
+            }
+        }
+        s
+    }
+
+    /// The visible digits as three lines of ASCII art.
+    pub fn render(&self) -> String {
+        let mut rows = [String::new(), String::new(), String::new()];
+        for digit in 0..self.digits.len() {
+            let seg = self.segments(digit);
+            let on = |bit: u8, c: char| if seg & bit != 0 { c } else { ' ' };
+            rows[0].extend([' ', on(SEG_A, '_'), ' ', ' ']);
+            rows[1].extend([on(SEG_F, '|'), on(SEG_G, '_'), on(SEG_B, '|'), ' ']);
+            rows[2].extend([
+                on(SEG_E, '|'),
+                on(SEG_D, '_'),
+                on(SEG_C, '|'),
+                on(SEG_DP, '.'),
+            ]);
+        }
+        rows.join("\n")
+    }
+
+    fn light(&mut self, digit: usize, segments: u8) {
+        if let Some(d) = self.digits.get_mut(digit) {
+            *d = Digit {
+                segments,
+                lit_at: Some(self.now),
+            };
+        }
+    }
+}
+
+impl IoDevice for SegmentDisplay {
+    fn write4(&mut self, nibble: u8) {
+        self.state = match self.state {
+            State::Select => match self.mode {
+                Mode::Bcd => State::Bcd {
+                    digit: nibble as usize,
+                },
+                Mode::Segments => State::SegHi {
+                    digit: nibble as usize,
+                },
+            },
+            State::Bcd { digit } => {
+                let segments = match nibble {
+                    0..=9 => DIGITS[nibble as usize],
+                    0xA => SEG_G,
+                    _ => 0,
+                };
+                self.light(digit, segments);
+                State::Select
+            }
+            State::SegHi { digit } => State::SegLo { digit, hi: nibble },
+            State::SegLo { digit, hi } => {
+                self.light(digit, (hi << 4) | nibble);
+                State::Select
+            }
+        };
+    }
+
+    fn tick(&mut self, cpu: &Cpu4004) {
+        self.now = cpu.cycles();
+    }
+}
@@ -0,0 +1,298 @@
+//! Monochrome dot-matrix framebuffer.
+//!
+//! A [`Framebuffer`] on a ROM port holds a grid of pixels (32×16 by default,
+//! up to 256×256) that the ROM addresses with column and row nibbles and
+//! draws four pixels at a time. Frames can be checked as text in tests, or
+//! saved as PPM or PNG images without any extra dependency.
+//!
+//! # Commands (write4 / WRR)
+//!
+//! ```text
+//! 1 hi lo   COLUMN    move the cursor to column hi:lo
+//! 2 hi lo   ROW       move the cursor to row hi:lo
+//! 3 n       PLOT      draw n's bits on 4 pixels from the cursor, bit 3
+//!                     leftmost, then move the cursor 4 columns right
+//! 4         SET       light the pixel under the cursor
+//! 5         RESET     darken the pixel under the cursor
+//! 6         CLEAR     darken every pixel
+//! 7         PRESENT   mark the frame as complete
+//! ```
+//!
+//! Pixels outside the grid are ignored. Every read (`RDR`) returns the four
+//! pixels from the cursor in the same layout as PLOT, so a ROM can scroll or
+//! merge graphics; reading does not move the cursor.
+//!
+//! # Example
+//!
+//! ```
+//! use intel_4004::dev::IoDevice;
+//! use intel_4004::dev::framebuffer::Framebuffer;
+//!
+//! let mut fb = Framebuffer::new(8, 2);
+//! for n in [2, 0, 1, 3, 0xA] {
+//!     fb.write4(n); // ROW 01; PLOT A
+//! }
+//! assert_eq!(fb.render(), "........\n#.#.....\n");
+//! ```
+
+use std::path::Path;
+
+use crate::dev::IoDevice;
+
+pub const COLUMN: u8 = 0x1;
+pub const ROW: u8 = 0x2;
+pub const PLOT: u8 = 0x3;
+pub const SET: u8 = 0x4;
+pub const RESET: u8 = 0x5;
+pub const CLEAR: u8 = 0x6;
+pub const PRESENT: u8 = 0x7;
+
+#[derive(Default)]
+enum State {
+    #[default]
+    Command,
+    Operand {
+        cmd: u8,
+        hi: Option<u8>,
+    },
+}
+
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>,
+    x: usize,
+    y: usize,
+    state: State,
+    presented: Vec<bool>,
+    frames: u64,
+}
+
+impl Default for Framebuffer {
+    fn default() -> Self {
+        Self::new(32, 16)
+    }
+}
+
+impl Framebuffer {
+    /// A dark framebuffer of `width` × `height` pixels, each between 1 and 256.
+    pub fn new(width: usize, height: usize) -> Self {
+        let (width, height) = (width.clamp(1, 256), height.clamp(1, 256));
+        Self {
+            width,
+            height,
+            pixels: vec![false; width * height],
+            x: 0,
+            y: 0,
+            state: State::Command,
+            presented: vec![false; width * height],
+            frames: 0,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.pixels[y * self.width + x]
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, on: bool) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = on;
+        }
+    }
+
+    /// Number of PRESENT commands so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// The frame as it was at the last PRESENT, rendered like [`Framebuffer::render`].
+    pub fn presented(&self) -> String {
+        self.render_pixels(&self.presented)
+    }
+
+    /// The current pixels, one line per row: `#` lit, `.` dark.
+    pub fn render(&self) -> String {
+        self.render_pixels(&self.pixels)
+    }
+
+    /// A binary PPM (P6) image, each pixel drawn as a `scale` × `scale` square.
+    pub fn to_ppm(&self, scale: usize) -> Vec<u8> {
+        let (w, h, gray) = self.scaled(scale);
+        let mut out = format!("P6\n{w} {h}\n255\n").into_bytes();
+        out.extend(gray.iter().flat_map(|&v| [v, v, v]));
+        out
+    }
+
+    /// A grayscale PNG image, each pixel drawn as a `scale` × `scale` square.
+    ///
+    /// The image data is stored uncompressed, which keeps the encoder small;
+    /// dot-matrix frames are tiny anyway.
+    pub fn to_png(&self, scale: usize) -> Vec<u8> {
+        let (w, h, gray) = self.scaled(scale);
+        let mut raw = Vec::with_capacity((w + 1) * h);
+        for row in gray.chunks(w) {
+            raw.push(0); // filter: none
+            raw.extend(row);
+        }
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend((w as u32).to_be_bytes());
+        ihdr.extend((h as u32).to_be_bytes());
+        ihdr.extend([8, 0, 0, 0, 0]); // 8-bit grayscale
+        let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+        png_chunk(&mut out, b"IHDR", &ihdr);
+        png_chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+        png_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+
+    pub fn write_ppm(&self, path: impl AsRef<Path>, scale: usize) -> std::io::Result<()> {
+        std::fs::write(path, self.to_ppm(scale))
+    }
+
+    pub fn write_png(&self, path: impl AsRef<Path>, scale: usize) -> std::io::Result<()> {
+        std::fs::write(path, self.to_png(scale))
+    }
+
+    fn render_pixels(&self, pixels: &[bool]) -> String {
+        let mut out = String::with_capacity((self.width + 1) * self.height);
+        for row in pixels.chunks(self.width) {
+            out.extend(row.iter().map(|&on| if on { '#' } else { '.' }));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Image size and 8-bit gray levels of the current pixels, scaled up.
+    fn scaled(&self, scale: usize) -> (usize, usize, Vec<u8>) {
+        let scale = scale.max(1);
+        let (w, h) = (self.width * scale, self.height * scale);
+        let mut gray = Vec::with_capacity(w * h);
+        for y in 0..h {
+            for x in 0..w {
+                gray.push(if self.pixel(x / scale, y / scale) {
+                    255
+                } else {
+                    0
+                });
+            }
+        }
+        (w, h, gray)
+    }
+
+    fn execute(&mut self, cmd: u8, operand: u8) {
+        match cmd {
+            COLUMN => self.x = operand as usize,
+            ROW => self.y = operand as usize,
+            PLOT => {
+                for i in 0..4 {
+                    self.set_pixel(self.x + i, self.y, operand & (8 >> i) != 0);
+                }
+                self.x += 4;
+            }
+            SET => self.set_pixel(self.x, self.y, true),
+            RESET => self.set_pixel(self.x, self.y, false),
+            CLEAR => self.pixels.fill(false),
+            PRESENT => {
+                self.presented.copy_from_slice(&self.pixels);
+                self.frames += 1;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl IoDevice for Framebuffer {
+    fn write4(&mut self, nibble: u8) {
+        let nibble = nibble & 0xF;
+        self.state = match std::mem::take(&mut self.state) {
+            State::Command => match nibble {
+                COLUMN | ROW | PLOT => State::Operand {
+                    cmd: nibble,
+                    hi: None,
+                },
+                _ => {
+                    self.execute(nibble, 0);
+                    State::Command
+                }
+            },
+            State::Operand { cmd: PLOT, .. } => {
+                self.execute(PLOT, nibble);
+                State::Command
+            }
+            State::Operand { cmd, hi: None } => State::Operand {
+                cmd,
+                hi: Some(nibble),
+            },
+            State::Operand { cmd, hi: Some(hi) } => {
+                self.execute(cmd, hi << 4 | nibble);
+                State::Command
+            }
+        };
+    }
+
+    fn read4(&mut self) -> u8 {
+        (0..4).fold(0, |n, i| {
+            n | (self.pixel(self.x + i, self.y) as u8) << (3 - i)
+        })
+    }
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend(kind);
+    out.extend(data);
+    let crc = crc32(&out[start..]);
+    out.extend(crc.to_be_bytes());
+}
+
+/// A zlib stream of uncompressed (stored) deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(0xFFFF).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none() as u8;
+        let len = block.len() as u16;
+        out.push(last);
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend(block);
+    }
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
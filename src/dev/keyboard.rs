@@ -0,0 +1,194 @@
+//! Key matrix scanned by the ROM, for `KBP`-based keyboard routines.
+//!
+//! The ROM drives one column at a time, either with the nibble it writes to
+//! the device's port or through the outputs of a 4003 chain, and reads back
+//! the rows of that column through `RDR`. A single pressed key yields the
+//! one-hot nibble `KBP` converts into a row index.
+//!
+//! # Port (write4 / read4)
+//!
+//! ```text
+//! write  columns 0–3, one bit each (only with Columns::Port)
+//! read   rows 0–3 of the active columns, one bit each
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! use intel_4004::chips::{DataRam4002, Rom4001, ShiftRegister4003};
+//! use intel_4004::dev::keyboard::{Columns, KeyMatrix};
+//! use intel_4004::dev::shared;
+//!
+//! let scan = shared(ShiftRegister4003::new(1));
+//! let keys = shared(
+//!     KeyMatrix::new(Columns::ShiftRegister(scan.clone()))
+//!         .with_key("1", 0, 0)
+//!         .with_key("+", 8, 3),
+//! );
+//!
+//! let mut ram = DataRam4002::default();
+//! ram.attach_port(scan);
+//! let mut rom = Rom4001::from_bytes(&[/* firmware */]);
+//! rom.attach_port(keys.clone());
+//!
+//! keys.borrow_mut().tap("1").unwrap();
+//! ```
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use crate::chips::{Cpu4004, ShiftRegister4003};
+use crate::dev::{IoDevice, Shared};
+
+/// Where the column select lines come from.
+pub enum Columns {
+    /// The nibble last written to the device's own port.
+    Port,
+    /// The parallel outputs of a 4003 chain.
+    ShiftRegister(Shared<ShiftRegister4003>),
+}
+
+/// A key name that is not in the layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownKey(pub String);
+
+impl fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown key {:?}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownKey {}
+
+#[derive(Clone, Copy)]
+enum Event {
+    Press((usize, u8)),
+    Release((usize, u8)),
+}
+
+pub struct KeyMatrix {
+    columns: Columns,
+    port_columns: u8,
+    layout: HashMap<String, (usize, u8)>,
+    pressed: HashSet<(usize, u8)>,
+    queue: VecDeque<(Event, u64)>,
+    hold_cycles: u64,
+    debounce_cycles: u64,
+    now: u64,
+    next_event: u64,
+}
+
+impl KeyMatrix {
+    pub fn new(columns: Columns) -> Self {
+        Self {
+            columns,
+            port_columns: 0,
+            layout: HashMap::new(),
+            pressed: HashSet::new(),
+            queue: VecDeque::new(),
+            hold_cycles: 20_000,     // ~27 ms at 740 kHz
+            debounce_cycles: 20_000, // settle time before the next key
+            now: 0,
+            next_event: 0,
+        }
+    }
+
+    /// Names the key at `column`, `row` (0–3).
+    pub fn with_key(mut self, name: &str, column: usize, row: u8) -> Self {
+        self.layout.insert(name.to_string(), (column, row & 0x3));
+        self
+    }
+
+    /// Sets how long a tapped key stays down, and the quiet time after it, in clock cycles.
+    pub fn with_timing(mut self, hold_cycles: u64, debounce_cycles: u64) -> Self {
+        self.hold_cycles = hold_cycles;
+        self.debounce_cycles = debounce_cycles;
+        self
+    }
+
+    /// Holds a key down until [`Self::release`].
+    pub fn press(&mut self, key: &str) -> Result<(), UnknownKey> {
+        let pos = self.position(key)?;
+        self.pressed.insert(pos);
+        Ok(())
+    }
+
+    pub fn release(&mut self, key: &str) -> Result<(), UnknownKey> {
+        let pos = self.position(key)?;
+        self.pressed.remove(&pos);
+        Ok(())
+    }
+
+    /// Whether `key` is held down; `false` for a key not in the layout.
+    pub fn is_pressed(&self, key: &str) -> bool {
+        self.position(key)
+            .is_ok_and(|pos| self.pressed.contains(&pos))
+    }
+
+    /// Queues a press and release of `key`, after any keys queued before it.
+    pub fn tap(&mut self, key: &str) -> Result<(), UnknownKey> {
+        let pos = self.position(key)?;
+        self.queue.push_back((Event::Press(pos), self.hold_cycles));
+        self.queue
+            .push_back((Event::Release(pos), self.debounce_cycles));
+        Ok(())
+    }
+
+    /// Queues a tap for each key in turn. Nothing is queued if any key is
+    /// not in the layout.
+    pub fn type_keys(&mut self, keys: &[&str]) -> Result<(), UnknownKey> {
+        for key in keys {
+            self.position(key)?;
+        }
+        for key in keys {
+            self.tap(key)?;
+        }
+        Ok(())
+    }
+
+    /// Whether queued keys are still waiting to be played.
+    pub fn is_busy(&self) -> bool {
+        !self.queue.is_empty() || self.now < self.next_event
+    }
+
+    fn position(&self, key: &str) -> Result<(usize, u8), UnknownKey> {
+        self.layout
+            .get(key)
+            .copied()
+            .ok_or_else(|| UnknownKey(key.to_string()))
+    }
+
+    fn column_active(&self, column: usize) -> bool {
+        match &self.columns {
+            Columns::Port => column < 4 && self.port_columns & (1 << column) != 0,
+            Columns::ShiftRegister(sr) => sr.borrow().output(column),
+        }
+    }
+}
+
+impl IoDevice for KeyMatrix {
+    fn write4(&mut self, nibble: u8) {
+        self.port_columns = nibble;
+    }
+
+    fn read4(&mut self) -> u8 {
+        self.pressed
+            .iter()
+            .filter(|(column, _)| self.column_active(*column))
+            .fold(0, |rows, (_, row)| rows | (1 << row))
+    }
+
+    fn tick(&mut self, cpu: &Cpu4004) {
+        self.now = cpu.cycles();
+        while self.now >= self.next_event {
+            let Some((event, delay)) = self.queue.pop_front() else {
+                break;
+            };
+            match event {
+                Event::Press(pos) => self.pressed.insert(pos),
+                Event::Release(pos) => self.pressed.remove(&pos),
+            };
+            self.next_event = self.now + delay;
+        }
+    }
+}
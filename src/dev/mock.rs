@@ -0,0 +1,197 @@
+//! Scriptable device for testing ROM I/O.
+//!
+//! A [`MockDevice`] plays back a script of port accesses: nibbles the ROM is
+//! expected to write, and nibbles to answer its reads with, in order. It
+//! panics on the first access that does not match, naming the PC of the
+//! instruction, and records every access with its PC and cycle so tests can
+//! check timing too.
+//!
+//! # Example
+//!
+//! ```
+//! use intel_4004::bus::simple::SimpleBus;
+//! use intel_4004::chips::{DataRam4002, Rom4001};
+//! use intel_4004::dev::mock::MockDevice;
+//! use intel_4004::dev::shared;
+//! use intel_4004::machine::Machine;
+//!
+//! // RDR; WRR: echo one nibble
+//! let mock = shared(MockDevice::new().expect_read(0x7).expect_write(0x7));
+//! let mut rom = Rom4001::from_bytes(&[0xEA, 0xE2]);
+//! rom.attach_port(mock.clone());
+//! let mut m = Machine::new(SimpleBus::new(rom, DataRam4002::default()));
+//! m.run_steps(2);
+//! mock.borrow().assert_done();
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::chips::Cpu4004;
+use crate::dev::IoDevice;
+
+/// One port access, with the nibble written or returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    Write(u8),
+    Read(u8),
+}
+
+/// An access as it happened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Record {
+    pub access: Access,
+    /// Address of the instruction that made the access.
+    pub pc: u16,
+    /// Cycle count when that instruction started.
+    pub cycle: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Step {
+    Write(Option<u8>),
+    Read(u8),
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Write(Some(n)) => write!(f, "write {n:X}"),
+            Step::Write(None) => write!(f, "any write"),
+            Step::Read(n) => write!(f, "read (answering {n:X})"),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MockDevice {
+    script: VecDeque<Step>,
+    default_read: Option<u8>,
+    records: Vec<Record>,
+    pc: u16,
+    cycle: u64,
+}
+
+impl MockDevice {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expects the ROM to write `nibble` next.
+    pub fn expect_write(mut self, nibble: u8) -> Self {
+        self.script.push_back(Step::Write(Some(nibble & 0xF)));
+        self
+    }
+
+    pub fn expect_writes(mut self, nibbles: &[u8]) -> Self {
+        for &n in nibbles {
+            self = self.expect_write(n);
+        }
+        self
+    }
+
+    /// Expects a write of any value next.
+    pub fn expect_any_write(mut self) -> Self {
+        self.script.push_back(Step::Write(None));
+        self
+    }
+
+    /// Expects the ROM to read next, and answers `nibble`.
+    pub fn expect_read(mut self, nibble: u8) -> Self {
+        self.script.push_back(Step::Read(nibble & 0xF));
+        self
+    }
+
+    pub fn expect_reads(mut self, nibbles: &[u8]) -> Self {
+        for &n in nibbles {
+            self = self.expect_read(n);
+        }
+        self
+    }
+
+    /// Answers reads with `nibble` once the script is over, instead of panicking.
+    pub fn with_default_read(mut self, nibble: u8) -> Self {
+        self.default_read = Some(nibble & 0xF);
+        self
+    }
+
+    /// Every access so far, oldest first.
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Nibbles written so far, in order.
+    pub fn writes(&self) -> Vec<u8> {
+        self.records
+            .iter()
+            .filter_map(|r| match r.access {
+                Access::Write(n) => Some(n),
+                Access::Read(_) => None,
+            })
+            .collect()
+    }
+
+    /// Steps of the script not played yet.
+    pub fn remaining(&self) -> usize {
+        self.script.len()
+    }
+
+    /// Panics unless the whole script has been played.
+    pub fn assert_done(&self) {
+        if let Some(step) = self.script.front() {
+            panic!(
+                "MockDevice: {} step(s) left, next is {step}, after {} access(es)",
+                self.script.len(),
+                self.records.len()
+            );
+        }
+    }
+
+    fn record(&mut self, access: Access) {
+        self.records.push(Record {
+            access,
+            pc: self.pc,
+            cycle: self.cycle,
+        });
+    }
+
+    fn mismatch(&self, expected: Option<Step>, got: &str) -> ! {
+        let expected = expected.map_or("nothing".to_string(), |s| s.to_string());
+        panic!(
+            "MockDevice: expected {expected}, got {got} at PC {:03X} (cycle {})",
+            self.pc, self.cycle
+        );
+    }
+}
+
+impl IoDevice for MockDevice {
+    fn write4(&mut self, nibble: u8) {
+        match self.script.front().copied() {
+            Some(Step::Write(None)) => {}
+            Some(Step::Write(Some(n))) if n == nibble => {}
+            step => self.mismatch(step, &format!("write {nibble:X}")),
+        }
+        self.script.pop_front();
+        self.record(Access::Write(nibble));
+    }
+
+    fn read4(&mut self) -> u8 {
+        let nibble = match self.script.front().copied() {
+            Some(Step::Read(n)) => {
+                self.script.pop_front();
+                n
+            }
+            None if self.default_read.is_some() => self.default_read.unwrap(),
+            step => self.mismatch(step, "read"),
+        };
+        self.record(Access::Read(nibble));
+        nibble
+    }
+
+    /// After each instruction the CPU's PC and cycle count point at the next
+    /// one, which is the instruction making the next access.
+    fn tick(&mut self, cpu: &Cpu4004) {
+        self.pc = cpu.pc();
+        self.cycle = cpu.cycles();
+    }
+}
@@ -1,4 +1,52 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+
+use crate::chips::Cpu4004;
+use crate::scheduler::Scheduler;
+
+pub mod display;
+pub mod framebuffer;
+pub mod keyboard;
+pub mod mock;
+pub(crate) mod nibble;
+pub mod panel;
+pub mod printer;
+// Its open(2) flags and termios layout are the generic Linux ones; other
+// architectures differ.
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "arm",
+        target_arch = "aarch64",
+        target_arch = "riscv32",
+        target_arch = "riscv64"
+    )
+))]
+pub mod pty;
+pub mod random;
+pub mod speaker;
+pub mod splitter;
+pub mod tape;
+pub mod tcp;
 pub mod terminal;
+pub mod timer;
+// Same targets as `pty`.
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "arm",
+        target_arch = "aarch64",
+        target_arch = "riscv32",
+        target_arch = "riscv64"
+    )
+))]
+pub(crate) mod tty;
+pub mod uart;
 pub mod udp;
 
 pub trait IoDevice {
@@ -7,4 +55,148 @@ pub trait IoDevice {
     fn read4(&mut self) -> u8 {
         0
     }
+
+    /// Called after every instruction, for devices that work on their own time.
+    fn tick(&mut self, _cpu: &Cpu4004) {}
+
+    /// Called once the device is both attached to a port and plugged into a
+    /// machine, with the lines it may drive.
+    fn connect(&mut self, _ctx: &DeviceContext) {}
+}
+
+/// Which I/O port a device sits on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PortId {
+    /// The 4001 ROM port.
+    Rom,
+    /// The 4002 RAM output port.
+    Ram,
+    /// One of the 16 ports of a 4289.
+    Io(u8),
+}
+
+impl fmt::Display for PortId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortId::Rom => write!(f, "ROM"),
+            PortId::Ram => write!(f, "RAM"),
+            PortId::Io(n) => write!(f, "IO{n:X}"),
+        }
+    }
+}
+
+/// What a device can reach of the machine it is plugged into.
+///
+/// The cycle count comes with every [`IoDevice::tick`]; the context adds the
+/// port the device sits on and the machine's lines: TEST, INT and a stop
+/// request that ends [`Machine::run_steps`](crate::machine::Machine::run_steps)
+/// and [`Machine::run_until`](crate::machine::Machine::run_until) early,
+/// and the machine's [`Scheduler`] to register timers with.
+#[derive(Clone, Debug)]
+pub struct DeviceContext {
+    port: Option<PortId>,
+    test: Line,
+    int: Line,
+    stop: Line,
+    scheduler: Scheduler,
+}
+
+impl DeviceContext {
+    pub fn new(test: Line, int: Line, stop: Line) -> Self {
+        Self {
+            port: None,
+            test,
+            int,
+            stop,
+            scheduler: Scheduler::new(),
+        }
+    }
+
+    /// Hands out `scheduler` instead of a fresh one.
+    pub fn with_scheduler(mut self, scheduler: Scheduler) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    /// The same lines, for the device on `port`.
+    pub fn at(&self, port: PortId) -> Self {
+        Self {
+            port: Some(port),
+            ..self.clone()
+        }
+    }
+
+    /// The port the device sits on; `None` before the context reaches a port.
+    pub fn port(&self) -> Option<PortId> {
+        self.port
+    }
+
+    /// The CPU's TEST pin, idle high.
+    pub fn test_line(&self) -> Line {
+        self.test.clone()
+    }
+
+    /// The INT line, idle low. The 4004 has no interrupt input, so nothing in
+    /// the core services it; the host polls it through the machine.
+    pub fn int_line(&self) -> Line {
+        self.int.clone()
+    }
+
+    /// The machine's scheduler, which keeps device timers on its clock.
+    pub fn scheduler(&self) -> &Scheduler {
+        &self.scheduler
+    }
+
+    /// Asks the machine to stop after the current instruction.
+    pub fn request_stop(&self) {
+        self.stop.set(true);
+    }
+}
+
+/// A shared logic level, e.g. the CPU's TEST pin driven by a device.
+#[derive(Clone, Default, Debug)]
+pub struct Line(Rc<Cell<bool>>);
+
+impl Line {
+    pub fn new(level: bool) -> Self {
+        Self(Rc::new(Cell::new(level)))
+    }
+
+    pub fn get(&self) -> bool {
+        self.0.get()
+    }
+
+    pub fn set(&self, level: bool) {
+        self.0.set(level);
+    }
+
+    /// Returns the level and pulls the line low.
+    pub fn take(&self) -> bool {
+        self.0.take()
+    }
+}
+
+/// A device kept by the host while attached to a port, e.g. to inspect its state.
+pub type Shared<T> = Rc<RefCell<T>>;
+
+pub fn shared<T>(dev: T) -> Shared<T> {
+    Rc::new(RefCell::new(dev))
+}
+
+impl<T: IoDevice + ?Sized> IoDevice for Rc<RefCell<T>> {
+    fn write4(&mut self, nibble: u8) {
+        self.borrow_mut().write4(nibble);
+    }
+
+    fn read4(&mut self) -> u8 {
+        self.borrow_mut().read4()
+    }
+
+    fn tick(&mut self, cpu: &Cpu4004) {
+        self.borrow_mut().tick(cpu);
+    }
+
+    fn connect(&mut self, ctx: &DeviceContext) {
+        self.borrow_mut().connect(ctx);
+    }
 }
@@ -0,0 +1,67 @@
+use std::collections::VecDeque;
+
+/// Status bit set when a byte is ready to be read.
+pub const AVAILABLE: u8 = 0b0001;
+
+/// Assembles bytes written as a high nibble followed by a low nibble.
+#[derive(Default)]
+pub(crate) struct ByteWriter {
+    hi: Option<u8>,
+}
+
+impl ByteWriter {
+    pub fn push(&mut self, nibble: u8) -> Option<u8> {
+        match self.hi.take() {
+            None => {
+                self.hi = Some(nibble & 0xF);
+                None
+            }
+            Some(hi) => Some((hi << 4) | (nibble & 0xF)),
+        }
+    }
+}
+
+#[derive(Default)]
+enum ReadState {
+    #[default]
+    Status,
+    Hi(u8),
+    Lo(u8),
+}
+
+/// Serves bytes as a status nibble, then the byte's high and low nibbles.
+///
+/// Reading the status takes the next byte off the queue and sets
+/// [`AVAILABLE`]; the two following reads return that byte. Without data the
+/// status is returned again on the next read.
+#[derive(Default)]
+pub(crate) struct ByteReader {
+    state: ReadState,
+}
+
+impl ByteReader {
+    /// Whether the next read returns a status.
+    pub fn at_status(&self) -> bool {
+        matches!(self.state, ReadState::Status)
+    }
+
+    pub fn read(&mut self, queue: &mut VecDeque<u8>, status: u8) -> u8 {
+        match self.state {
+            ReadState::Status => match queue.pop_front() {
+                Some(byte) => {
+                    self.state = ReadState::Hi(byte);
+                    status | AVAILABLE
+                }
+                None => status & !AVAILABLE,
+            },
+            ReadState::Hi(byte) => {
+                self.state = ReadState::Lo(byte);
+                byte >> 4
+            }
+            ReadState::Lo(byte) => {
+                self.state = ReadState::Status;
+                byte & 0xF
+            }
+        }
+    }
+}
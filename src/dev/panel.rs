@@ -0,0 +1,212 @@
+//! Blinkenlights: LEDs, toggle switches and a text front panel.
+//!
+//! [`LedBank`] shows the nibble last written to its port on four LEDs and
+//! remembers what was written when. [`SwitchPanel`] holds four switches the
+//! host sets, returned by every `RDR`. [`render`] draws the CPU state and
+//! the output latch of every port, for teaching or a quick look at a run.
+//!
+//! # Example
+//!
+//! ```
+//! use intel_4004::bus::simple::SimpleBus;
+//! use intel_4004::chips::{DataRam4002, Rom4001};
+//! use intel_4004::dev::panel::{self, LedBank, SwitchPanel};
+//! use intel_4004::dev::shared;
+//! use intel_4004::machine::Machine;
+//!
+//! // RDR; WMP; JUN 000H
+//! let mut rom = Rom4001::from_bytes(&[0xEA, 0xE1, 0x40, 0x00]);
+//! let switches = shared(SwitchPanel::new());
+//! rom.attach_port(switches.clone());
+//! let leds = shared(LedBank::new());
+//! let mut ram = DataRam4002::default();
+//! ram.attach_port(leds.clone());
+//! let mut m = Machine::new(SimpleBus::new(rom, ram));
+//!
+//! switches.borrow_mut().set(0b1010);
+//! m.run_steps(2);
+//! assert_eq!(leds.borrow().render(), "*.*.");
+//! println!("{}", panel::render(&m));
+//! ```
+
+use std::collections::VecDeque;
+
+use crate::bus::Bus;
+use crate::chips::Cpu4004;
+use crate::dev::IoDevice;
+use crate::machine::Machine;
+
+const LIT: char = '*';
+const DARK: char = '.';
+
+/// Four LEDs latching each nibble written to the port.
+pub struct LedBank {
+    value: u8,
+    history: VecDeque<(u64, u8)>,
+    capacity: usize,
+    now: u64,
+}
+
+impl Default for LedBank {
+    fn default() -> Self {
+        Self {
+            value: 0,
+            history: VecDeque::new(),
+            capacity: 256,
+            now: 0,
+        }
+    }
+}
+
+impl LedBank {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps the last `n` writes (256 by default).
+    pub fn with_history(mut self, n: usize) -> Self {
+        self.capacity = n;
+        self
+    }
+
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    pub fn is_lit(&self, bit: u8) -> bool {
+        self.value & (1 << (bit & 3)) != 0
+    }
+
+    /// Values written, oldest first, with the cycle of the instruction that wrote them.
+    pub fn history(&self) -> impl Iterator<Item = (u64, u8)> + '_ {
+        self.history.iter().copied()
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// The LEDs, bit 3 on the left: `*` lit, `.` dark.
+    pub fn render(&self) -> String {
+        leds(self.value)
+    }
+}
+
+impl IoDevice for LedBank {
+    fn write4(&mut self, nibble: u8) {
+        self.value = nibble & 0xF;
+        if self.capacity > 0 {
+            if self.history.len() == self.capacity {
+                self.history.pop_front();
+            }
+            self.history.push_back((self.now, self.value));
+        }
+    }
+
+    fn tick(&mut self, cpu: &Cpu4004) {
+        self.now = cpu.cycles();
+    }
+}
+
+/// Four toggle switches set by the host and read by the ROM.
+#[derive(Default)]
+pub struct SwitchPanel {
+    value: u8,
+}
+
+impl SwitchPanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_value(mut self, value: u8) -> Self {
+        self.set(value);
+        self
+    }
+
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    pub fn set(&mut self, value: u8) {
+        self.value = value & 0xF;
+    }
+
+    pub fn set_switch(&mut self, bit: u8, on: bool) {
+        let mask = 1 << (bit & 3);
+        self.value = if on {
+            self.value | mask
+        } else {
+            self.value & !mask
+        };
+    }
+
+    pub fn flip(&mut self, bit: u8) {
+        self.value ^= 1 << (bit & 3);
+    }
+
+    /// The switches, bit 3 on the left: `^` on, `v` off.
+    pub fn render(&self) -> String {
+        glyphs(self.value, '^', 'v')
+    }
+}
+
+impl IoDevice for SwitchPanel {
+    fn write4(&mut self, _nibble: u8) {}
+
+    fn read4(&mut self) -> u8 {
+        self.value
+    }
+}
+
+/// Draws the machine's front panel: PC, ACC, carry, the sixteen index
+/// registers and every port's output latch, each nibble as four LEDs.
+///
+/// ```text
+/// PC  002  ACC  *.*. A  CY .
+/// R0  .... 0  R1  .... 0  R2  .... 0  R3  .... 0
+/// …
+/// ROM .... 0  RAM *.*. A
+/// ```
+pub fn render<B: Bus>(m: &Machine<B>) -> String {
+    let cpu = m.cpu();
+    let mut out = format!(
+        "PC  {:03X}  ACC  {}  CY {}\n",
+        cpu.pc(),
+        nibble(cpu.acc()),
+        if cpu.cy() != 0 { LIT } else { DARK },
+    );
+    for row in 0..4 {
+        let regs: Vec<String> = (0..4)
+            .map(|col| row * 4 + col)
+            .map(|r| format!("{:<3} {}", format!("R{r}"), nibble(cpu.reg(r))))
+            .collect();
+        out += &regs.join("  ");
+        out.push('\n');
+    }
+    for ports in m.bus().port_latches().chunks(4) {
+        let ports: Vec<String> = ports
+            .iter()
+            .map(|(name, value)| format!("{name:<3} {}", nibble(*value)))
+            .collect();
+        out += &ports.join("  ");
+        out.push('\n');
+    }
+    out
+}
+
+fn leds(value: u8) -> String {
+    glyphs(value, LIT, DARK)
+}
+
+/// One glyph per bit, bit 3 first.
+fn glyphs(value: u8, on: char, off: char) -> String {
+    (0..4)
+        .rev()
+        .map(|bit| if value & (1 << bit) != 0 { on } else { off })
+        .collect()
+}
+
+fn nibble(value: u8) -> String {
+    format!("{} {:X}", leds(value), value & 0xF)
+}
@@ -0,0 +1,154 @@
+//! Busicom 141-PF drum printer (Shinshu Seiki Model 102).
+//!
+//! A continuously spinning drum carries 13 characters around each column.
+//! The drum reports each sector (character position) on the CPU's TEST pin
+//! and the start of a revolution as an index bit. The ROM shifts the columns
+//! to print into a 4003 chain, then fires the hammers while the wanted
+//! character is under them.
+//!
+//! # Port (write4 / read4)
+//!
+//! ```text
+//! write  bit 1   fire hammers of the columns set in the shift register
+//!        bit 3   advance paper, ending the current line
+//! read   bit 0   index pulse (sector 0)
+//! ```
+//!
+//! Hammers fire and the paper advances on the rising edge of their bit. On a
+//! Busicom board the hammer shift register sits on a ROM port and the printer
+//! on the RAM port.
+//!
+//! # Example
+//!
+//! ```
+//! use intel_4004::chips::{DataRam4002, Rom4001, ShiftRegister4003};
+//! use intel_4004::dev::printer::DrumPrinter;
+//! use intel_4004::dev::{Line, shared};
+//!
+//! let test = Line::new(true); // machine.test_line()
+//! let hammers = shared(ShiftRegister4003::new(2));
+//! let printer = shared(DrumPrinter::new(hammers.clone(), test));
+//!
+//! let mut rom = Rom4001::from_bytes(&[/* firmware */]);
+//! rom.attach_port(hammers);
+//! let mut ram = DataRam4002::default();
+//! ram.attach_port(printer.clone());
+//! ```
+
+use crate::chips::{Cpu4004, ShiftRegister4003};
+use crate::dev::{IoDevice, Line, Shared};
+
+pub const SECTORS: u64 = 13;
+pub const DIGIT_COLUMNS: usize = 15;
+pub const COLUMNS: usize = DIGIT_COLUMNS + 2;
+
+pub const FIRE: u8 = 0b0010;
+pub const ADVANCE: u8 = 0b1000;
+pub const INDEX: u8 = 0b0001;
+
+/// Characters under the hammers, by sector.
+const DIGIT_DRUM: [char; SECTORS as usize] = [
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', '.', '.', '-',
+];
+const SYMBOL_DRUM: [char; SECTORS as usize] = [
+    '◇', '+', '-', 'x', '÷', 'M', 'M', '^', '=', '√', '%', 'C', 'R',
+];
+
+pub struct DrumPrinter {
+    hammers: Shared<ShiftRegister4003>,
+    test: Line,
+    sector_cycles: u64,
+    pulse_cycles: u64,
+    now: u64,
+    last: u8,
+    line: [Option<char>; COLUMNS],
+    printed: Vec<String>,
+}
+
+impl DrumPrinter {
+    /// Creates a printer firing the columns set in `hammers`, reporting sectors on `test`.
+    pub fn new(hammers: Shared<ShiftRegister4003>, test: Line) -> Self {
+        Self {
+            hammers,
+            test,
+            sector_cycles: 20_000, // ~28 ms at 740 kHz
+            pulse_cycles: 5_000,
+            now: 0,
+            last: 0,
+            line: [None; COLUMNS],
+            printed: Vec::new(),
+        }
+    }
+
+    /// Sets the duration of a sector and of its TEST pulse, in clock cycles.
+    pub fn with_timing(mut self, sector_cycles: u64, pulse_cycles: u64) -> Self {
+        self.sector_cycles = sector_cycles.max(1);
+        self.pulse_cycles = pulse_cycles.min(self.sector_cycles);
+        self
+    }
+
+    /// The sector currently under the hammers.
+    pub fn sector(&self) -> u64 {
+        (self.now / self.sector_cycles) % SECTORS
+    }
+
+    /// Lines printed so far, without the one in progress.
+    pub fn lines(&self) -> &[String] {
+        &self.printed
+    }
+
+    /// Printed lines joined with newlines.
+    pub fn text(&self) -> String {
+        self.printed.join("\n")
+    }
+
+    fn in_pulse(&self) -> bool {
+        self.now % self.sector_cycles < self.pulse_cycles
+    }
+
+    fn fire(&mut self) {
+        let sector = self.sector() as usize;
+        let hammers = self.hammers.borrow();
+        for (col, slot) in self.line.iter_mut().enumerate() {
+            if hammers.output(col) {
+                *slot = Some(if col < DIGIT_COLUMNS {
+                    DIGIT_DRUM[sector]
+                } else {
+                    SYMBOL_DRUM[sector]
+                });
+            }
+        }
+    }
+
+    fn advance(&mut self) {
+        let line: String = self.line.iter().map(|c| c.unwrap_or(' ')).collect();
+        self.printed.push(line.trim_end().to_string());
+        self.line = [None; COLUMNS];
+    }
+}
+
+impl IoDevice for DrumPrinter {
+    fn write4(&mut self, nibble: u8) {
+        let rising = nibble & !self.last;
+        self.last = nibble;
+        if rising & FIRE != 0 {
+            self.fire();
+        }
+        if rising & ADVANCE != 0 {
+            self.advance();
+        }
+    }
+
+    fn read4(&mut self) -> u8 {
+        if self.sector() == 0 && self.in_pulse() {
+            INDEX
+        } else {
+            0
+        }
+    }
+
+    fn tick(&mut self, cpu: &Cpu4004) {
+        self.now = cpu.cycles();
+        self.test.set(self.in_pulse());
+    }
+}
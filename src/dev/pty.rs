@@ -0,0 +1,269 @@
+//! Pseudo-terminal serial device.
+//!
+//! Opens a Linux PTY and bridges it to a port, so that host tools (`screen`,
+//! `minicom`, `picocom`, a Python script…) can talk to the ROM as if it were
+//! on the other end of a serial cable. Connect them to [`PtyDevice::path`].
+//!
+//! Linux only, on x86, x86-64, ARM, AArch64 and RISC-V (32 and 64 bit): the
+//! `open(2)` flags below and the termios layout in `dev::tty` are the generic
+//! Linux ABI, which MIPS, PowerPC, SPARC and Alpha do not share. The module is
+//! not built on other targets.
+//!
+//! # Nibble mode (default)
+//!
+//! Bytes use the same nibble pairs as the [terminal](crate::dev::terminal):
+//!
+//! ```text
+//! write 0,1  high, low nibble of a byte sent to the host
+//! read 0     status, bit 0 set when a byte from the host is available,
+//!            bit 3 set when a write to the host failed (see take_error)
+//! read 1,2   high, low nibble of that byte
+//! ```
+//!
+//! Writes never block: a byte the PTY cannot take, e.g. because no client
+//! drains it, is counted as failed in [`PtyDevice::stats`].
+//!
+//! # UART mode
+//!
+//! With [`PtyDevice::with_uart`] the ROM bit-bangs an asynchronous serial
+//! line instead: one bit of each port write is the transmit line, and one bit
+//! of each port read is the receive line, idle high. Bit times are counted in
+//! emulated clock cycles (`CLOCK_HZ / baud`), so the ROM's delay loops set
+//! the baud rate exactly as on real hardware. Characters are 8N1, or any
+//! [`UartConfig`] format with [`PtyDevice::with_uart_config`]. Received
+//! characters with a bad stop or parity bit are dropped and counted in
+//! [`PtyDevice::errors`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use intel_4004::chips::Rom4001;
+//! use intel_4004::dev::pty::PtyDevice;
+//! use intel_4004::machine::CLOCK_HZ;
+//!
+//! let pty = PtyDevice::open().unwrap().with_uart(CLOCK_HZ / 110, 0, 0);
+//! println!("connect to {}", pty.path().display());
+//! let mut rom = Rom4001::from_bytes(&[/* your ROM bytes */]);
+//! rom.attach_port(pty);
+//! ```
+
+use std::collections::VecDeque;
+use std::ffi::{CStr, c_char, c_int};
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use crate::chips::Cpu4004;
+use crate::dev::IoDevice;
+use crate::dev::nibble::{ByteReader, ByteWriter};
+use crate::dev::tty::Termios;
+use crate::dev::uart::{SoftUart, UartConfig};
+
+pub use crate::dev::nibble::AVAILABLE;
+
+pub const ERROR: u8 = 0b1000;
+
+// The generic Linux values, shared by every architecture `pty` is built for;
+// `tests/pty.rs` checks that they take effect.
+const O_NOCTTY: c_int = 0o400;
+const O_NONBLOCK: c_int = 0o4000;
+
+unsafe extern "C" {
+    fn grantpt(fd: c_int) -> c_int;
+    fn unlockpt(fd: c_int) -> c_int;
+    fn ptsname_r(fd: c_int, buf: *mut c_char, len: usize) -> c_int;
+}
+
+/// Byte counters, since the PTY was opened.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PtyStats {
+    pub sent: u64,
+    /// Bytes the PTY refused, e.g. while its buffer is full.
+    pub failed: u64,
+    pub received: u64,
+}
+
+enum Mode {
+    Nibbles { out: ByteWriter, reader: ByteReader },
+    Uart(SoftUart),
+}
+
+pub struct PtyDevice {
+    master: File,
+    /// Held open so the master does not see a hangup while no client is connected.
+    _slave: File,
+    path: PathBuf,
+    input: VecDeque<u8>,
+    mode: Mode,
+    stats: PtyStats,
+    error: Option<std::io::Error>,
+}
+
+impl PtyDevice {
+    /// Creates a new PTY, in raw mode and nibble mode.
+    pub fn open() -> std::io::Result<Self> {
+        let master = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(O_NOCTTY | O_NONBLOCK)
+            .open("/dev/ptmx")?;
+        let fd = master.as_raw_fd();
+        let mut name = [0 as c_char; 128];
+        // SAFETY: `fd` is an open PTY master and `name` outlives the calls.
+        unsafe {
+            if grantpt(fd) != 0 || unlockpt(fd) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            let err = ptsname_r(fd, name.as_mut_ptr(), name.len());
+            if err != 0 {
+                return Err(std::io::Error::from_raw_os_error(err));
+            }
+        }
+        // SAFETY: ptsname_r succeeded, so `name` holds a NUL-terminated string.
+        let path = unsafe { CStr::from_ptr(name.as_ptr()) };
+        let path = PathBuf::from(path.to_string_lossy().into_owned());
+        let slave = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(O_NOCTTY)
+            .open(&path)?;
+        let mut termios = Termios::get(&slave)?;
+        termios.make_raw();
+        termios.set(&slave)?;
+        Ok(Self {
+            master,
+            _slave: slave,
+            path,
+            input: VecDeque::new(),
+            mode: Mode::Nibbles {
+                out: ByteWriter::default(),
+                reader: ByteReader::default(),
+            },
+            stats: PtyStats::default(),
+            error: None,
+        })
+    }
+
+    /// Switches to 8N1 UART mode with a bit time of `cycles_per_bit` clock
+    /// cycles: bit `tx_bit` of port writes is the ROM's transmit line and bit
+    /// `rx_bit` of port reads its receive line.
+    pub fn with_uart(self, cycles_per_bit: u64, tx_bit: u8, rx_bit: u8) -> Self {
+        self.with_uart_config(
+            UartConfig::with_cycles_per_bit(cycles_per_bit),
+            tx_bit,
+            rx_bit,
+        )
+    }
+
+    /// Like [`with_uart`](Self::with_uart), with any speed and character format.
+    pub fn with_uart_config(mut self, config: UartConfig, tx_bit: u8, rx_bit: u8) -> Self {
+        let uart = SoftUart::new(config)
+            .with_tx_bit(tx_bit)
+            .with_rx_bit(rx_bit);
+        self.mode = Mode::Uart(uart);
+        self
+    }
+
+    /// The slave side of the PTY, e.g. `/dev/pts/3`, for host tools to open.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Characters received in UART mode with a framing or parity error.
+    pub fn errors(&self) -> u64 {
+        match &self.mode {
+            Mode::Uart(uart) => uart.errors(),
+            Mode::Nibbles { .. } => 0,
+        }
+    }
+
+    pub fn stats(&self) -> PtyStats {
+        self.stats
+    }
+
+    /// The last failed write to the host, if any. It stays flagged in the
+    /// nibble-mode status until taken.
+    pub fn take_error(&mut self) -> Option<std::io::Error> {
+        self.error.take()
+    }
+
+    fn poll(&mut self) {
+        let mut buf = [0u8; 64];
+        loop {
+            match self.master.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    self.stats.received += n as u64;
+                    self.input.extend(&buf[..n]);
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                // WouldBlock, or EIO when every client has hung up
+                Err(_) => break,
+            }
+        }
+    }
+
+    fn send(&mut self, byte: u8) {
+        match self.master.write_all(&[byte]) {
+            Ok(()) => self.stats.sent += 1,
+            Err(e) => {
+                self.stats.failed += 1;
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+impl IoDevice for PtyDevice {
+    fn write4(&mut self, nibble: u8) {
+        match &mut self.mode {
+            Mode::Nibbles { out, .. } => {
+                if let Some(byte) = out.push(nibble) {
+                    self.send(byte);
+                }
+            }
+            Mode::Uart(uart) => uart.write4(nibble),
+        }
+    }
+
+    fn read4(&mut self) -> u8 {
+        match &self.mode {
+            Mode::Nibbles { reader, .. } if !reader.at_status() => {}
+            Mode::Uart(uart) if !uart.is_idle() => {}
+            _ => self.poll(),
+        }
+        match &mut self.mode {
+            Mode::Nibbles { reader, .. } => {
+                let error = if self.error.is_some() { ERROR } else { 0 };
+                reader.read(&mut self.input, error)
+            }
+            Mode::Uart(uart) => {
+                // One character at a time, so the next waits for the ROM to poll again.
+                if uart.is_idle()
+                    && let Some(byte) = self.input.pop_front()
+                {
+                    uart.send(&[byte]);
+                }
+                uart.read4()
+            }
+        }
+    }
+
+    fn tick(&mut self, cpu: &Cpu4004) {
+        let Mode::Uart(uart) = &mut self.mode else {
+            return;
+        };
+        uart.tick(cpu);
+        let mut received = Vec::new();
+        while let Some(result) = uart.recv() {
+            if let Ok(byte) = result {
+                received.push(byte);
+            }
+        }
+        for byte in received {
+            self.send(byte);
+        }
+    }
+}
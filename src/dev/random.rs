@@ -0,0 +1,216 @@
+//! Random number source.
+//!
+//! A [`RandomDevice`] returns a random nibble on every `RDR`. It runs a
+//! seeded SplitMix64 generator, so a given seed always gives the same
+//! nibbles; [`RandomDevice::from_entropy`] draws the seed from the host, and
+//! [`RandomDevice::seed`] tells which one it was so a run can be replayed.
+//!
+//! # Commands (write4 / WRR)
+//!
+//! ```text
+//! 1 n×4   RESEED      restart from a 16-bit seed, high nibble first;
+//!                     a seed of 0 draws a fresh one from the host
+//! 2       UNIFORM     nibbles 0–15 (the default)
+//! 3 n     RANGE       nibbles 0–n
+//! 4 p     BERNOULLI   1 with probability p/16, else 0
+//! 5       BELL        mean of four uniform nibbles, bell-shaped around 7
+//! ```
+//!
+//! [`RandomDevice::state`] and [`RandomDevice::restore`] capture and put
+//! back the seed, the generator and the distribution, so a saved machine
+//! keeps producing the same numbers.
+//!
+//! # Example
+//!
+//! ```
+//! use intel_4004::dev::IoDevice;
+//! use intel_4004::dev::random::RandomDevice;
+//!
+//! let mut a = RandomDevice::seeded(4004);
+//! let mut b = RandomDevice::seeded(4004);
+//! assert_eq!(a.read4(), b.read4());
+//! ```
+
+use std::collections::hash_map::RandomState;
+use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
+use std::io::Read;
+use std::time::SystemTime;
+
+use crate::dev::IoDevice;
+
+pub const RESEED: u8 = 0x1;
+pub const UNIFORM: u8 = 0x2;
+pub const RANGE: u8 = 0x3;
+pub const BERNOULLI: u8 = 0x4;
+pub const BELL: u8 = 0x5;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Distribution {
+    /// 0 to 15.
+    #[default]
+    Uniform,
+    /// 0 to n, inclusive.
+    Range(u8),
+    /// 1 with probability p/16.
+    Bernoulli(u8),
+    /// Mean of four uniform nibbles.
+    Bell,
+}
+
+/// Everything needed to resume the sequence where it was.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RngState {
+    pub seed: u64,
+    pub state: u64,
+    pub distribution: Distribution,
+}
+
+#[derive(Default)]
+enum Command {
+    #[default]
+    Idle,
+    Reseed(Vec<u8>),
+    Range,
+    Bernoulli,
+}
+
+pub struct RandomDevice {
+    seed: u64,
+    state: u64,
+    distribution: Distribution,
+    command: Command,
+}
+
+impl RandomDevice {
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            seed,
+            state: seed,
+            distribution: Distribution::Uniform,
+            command: Command::Idle,
+        }
+    }
+
+    /// Seeds the generator from the host's entropy source.
+    pub fn from_entropy() -> Self {
+        Self::seeded(host_seed())
+    }
+
+    pub fn with_distribution(mut self, distribution: Distribution) -> Self {
+        self.distribution = distribution;
+        self
+    }
+
+    /// The seed of the current sequence, to replay it with [`RandomDevice::seeded`].
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn distribution(&self) -> Distribution {
+        self.distribution
+    }
+
+    pub fn state(&self) -> RngState {
+        RngState {
+            seed: self.seed,
+            state: self.state,
+            distribution: self.distribution,
+        }
+    }
+
+    pub fn restore(&mut self, state: &RngState) {
+        self.seed = state.seed;
+        self.state = state.state;
+        self.distribution = state.distribution;
+        self.command = Command::Idle;
+    }
+
+    /// SplitMix64.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn next_nibble(&mut self) -> u8 {
+        (self.next_u64() >> 60) as u8
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        let seed = if seed == 0 { host_seed() } else { seed };
+        self.seed = seed;
+        self.state = seed;
+    }
+}
+
+impl IoDevice for RandomDevice {
+    fn write4(&mut self, nibble: u8) {
+        let nibble = nibble & 0xF;
+        self.command = match std::mem::take(&mut self.command) {
+            Command::Idle => match nibble {
+                RESEED => Command::Reseed(Vec::with_capacity(4)),
+                UNIFORM => {
+                    self.distribution = Distribution::Uniform;
+                    Command::Idle
+                }
+                RANGE => Command::Range,
+                BERNOULLI => Command::Bernoulli,
+                BELL => {
+                    self.distribution = Distribution::Bell;
+                    Command::Idle
+                }
+                _ => Command::Idle,
+            },
+            Command::Reseed(mut nibbles) => {
+                nibbles.push(nibble);
+                if nibbles.len() < 4 {
+                    Command::Reseed(nibbles)
+                } else {
+                    self.reseed(nibbles.iter().fold(0, |s, &n| s << 4 | n as u64));
+                    Command::Idle
+                }
+            }
+            Command::Range => {
+                self.distribution = Distribution::Range(nibble);
+                Command::Idle
+            }
+            Command::Bernoulli => {
+                self.distribution = Distribution::Bernoulli(nibble);
+                Command::Idle
+            }
+        };
+    }
+
+    fn read4(&mut self) -> u8 {
+        match self.distribution {
+            Distribution::Uniform => self.next_nibble(),
+            Distribution::Range(n) => ((self.next_u64() >> 32) % (n as u64 + 1)) as u8,
+            Distribution::Bernoulli(p) => (self.next_nibble() < p) as u8,
+            Distribution::Bell => {
+                ((0..4).map(|_| self.next_nibble() as u32).sum::<u32>() / 4) as u8
+            }
+        }
+    }
+}
+
+/// A non-zero seed from the OS, or from std's per-process hash keys and the
+/// time where there is no `/dev/urandom`.
+fn host_seed() -> u64 {
+    let mut buf = [0u8; 8];
+    let seed = match File::open("/dev/urandom").and_then(|mut f| f.read_exact(&mut buf)) {
+        Ok(()) => u64::from_le_bytes(buf),
+        Err(_) => {
+            let mut h = RandomState::new().build_hasher();
+            h.write_u128(
+                SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_nanos()),
+            );
+            h.finish()
+        }
+    };
+    seed.max(1)
+}
@@ -0,0 +1,179 @@
+//! One-bit speaker.
+//!
+//! A [`Speaker`] watches one bit of a port, the way a ROM toggling a
+//! transistor drives a loudspeaker, and turns it into 16-bit mono PCM at a
+//! chosen sample rate. Time comes from the CPU's cycle count at the nominal
+//! 740 kHz clock, so the pitch is the one the real chip would play, however
+//! fast the host runs it.
+//!
+//! Each sample is the average level of the bit over its period, from
+//! `-AMPLITUDE` (low) to `AMPLITUDE` (high). Samples are kept for
+//! [`Speaker::wav_bytes`] / [`Speaker::write_wav`], or instead streamed as
+//! raw little-endian PCM to a sink with [`Speaker::with_sink`], so a long run
+//! does not hold the whole recording in memory.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use intel_4004::chips::Rom4001;
+//! use intel_4004::dev::shared;
+//! use intel_4004::dev::speaker::Speaker;
+//!
+//! let speaker = shared(Speaker::new(44_100));
+//! let mut rom = Rom4001::from_bytes(&[/* your ROM bytes */]);
+//! rom.attach_port(speaker.clone());
+//! // … run the machine …
+//! speaker.borrow().write_wav("tone.wav").unwrap();
+//! ```
+
+use std::io::Write;
+use std::path::Path;
+
+use crate::chips::Cpu4004;
+use crate::dev::IoDevice;
+use crate::machine::CLOCK_HZ;
+
+/// Peak sample value.
+pub const AMPLITUDE: i16 = 0x3FFF;
+
+pub struct Speaker {
+    bit: u8,
+    sample_rate: u32,
+    clock_hz: u64,
+    level: bool,
+    /// Index of the sample being accumulated.
+    sample: u64,
+    /// Cycle up to which the level has been accumulated.
+    mark: u64,
+    high_cycles: u64,
+    samples: Vec<i16>,
+    sink: Option<Box<dyn Write>>,
+    now: u64,
+}
+
+impl Speaker {
+    /// A speaker on bit 0, sampled `sample_rate` times per emulated second.
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            bit: 0,
+            sample_rate: sample_rate.max(1),
+            clock_hz: CLOCK_HZ,
+            level: false,
+            sample: 0,
+            mark: 0,
+            high_cycles: 0,
+            samples: Vec::new(),
+            sink: None,
+            now: 0,
+        }
+    }
+
+    pub fn with_bit(mut self, bit: u8) -> Self {
+        self.bit = bit & 3;
+        self
+    }
+
+    /// Clock frequency the cycle count is converted with, for other crystals.
+    pub fn with_clock(mut self, hz: u64) -> Self {
+        self.clock_hz = hz.max(1);
+        self
+    }
+
+    /// Streams each sample to `sink` as 16-bit little-endian PCM instead of
+    /// keeping it. If the sink fails, samples are kept again from then on.
+    pub fn with_sink(mut self, sink: impl Write + 'static) -> Self {
+        self.sink = Some(Box::new(sink));
+        self
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn samples(&self) -> &[i16] {
+        &self.samples
+    }
+
+    /// Drops the samples kept so far, e.g. after writing them out.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// The samples as a mono 16-bit PCM WAV file.
+    pub fn wav_bytes(&self) -> Vec<u8> {
+        let data_len = self.samples.len() as u32 * 2;
+        let mut out = Vec::with_capacity(44 + data_len as usize);
+        out.extend(b"RIFF");
+        out.extend((36 + data_len).to_le_bytes());
+        out.extend(b"WAVEfmt ");
+        out.extend(16u32.to_le_bytes());
+        out.extend(1u16.to_le_bytes()); // PCM
+        out.extend(1u16.to_le_bytes()); // mono
+        out.extend(self.sample_rate.to_le_bytes());
+        out.extend((self.sample_rate * 2).to_le_bytes());
+        out.extend(2u16.to_le_bytes());
+        out.extend(16u16.to_le_bytes());
+        out.extend(b"data");
+        out.extend(data_len.to_le_bytes());
+        for s in &self.samples {
+            out.extend(s.to_le_bytes());
+        }
+        out
+    }
+
+    pub fn write_wav(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.wav_bytes())
+    }
+
+    /// First cycle of sample `n`.
+    fn boundary(&self, n: u64) -> u64 {
+        (n as u128 * self.clock_hz as u128 / self.sample_rate as u128) as u64
+    }
+
+    /// Accumulates the current level up to cycle `to`, emitting every sample that ends by then.
+    fn advance(&mut self, to: u64) {
+        loop {
+            let start = self.boundary(self.sample);
+            let end = self.boundary(self.sample + 1);
+            if end > to {
+                break;
+            }
+            if self.level {
+                self.high_cycles += end - self.mark;
+            }
+            let period = (end - start).max(1) as i64;
+            let high = self.high_cycles as i64;
+            let value = (2 * high - period) * AMPLITUDE as i64 / period;
+            self.emit(value as i16);
+            self.sample += 1;
+            self.mark = end;
+            self.high_cycles = 0;
+        }
+        if self.level {
+            self.high_cycles += to - self.mark;
+        }
+        self.mark = to;
+    }
+
+    fn emit(&mut self, sample: i16) {
+        if let Some(sink) = &mut self.sink {
+            if sink.write_all(&sample.to_le_bytes()).is_ok() {
+                return;
+            }
+            self.sink = None;
+        }
+        self.samples.push(sample);
+    }
+}
+
+impl IoDevice for Speaker {
+    fn write4(&mut self, nibble: u8) {
+        self.advance(self.now);
+        self.level = nibble & (1 << self.bit) != 0;
+    }
+
+    fn tick(&mut self, cpu: &Cpu4004) {
+        self.now = cpu.cycles();
+        self.advance(self.now);
+    }
+}
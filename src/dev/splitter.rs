@@ -0,0 +1,98 @@
+//! Several devices on one port.
+//!
+//! A port takes a single device, but real boards wire single bits to
+//! different peripherals: bit 0 to a UART line, bits 1 and 2 to a shift
+//! register, and so on. A [`PortSplitter`] is attached in their place and
+//! gives each device the bits of its mask:
+//!
+//! - a write passes `nibble & mask` to every device, bits left in place;
+//! - a read ORs together each device's `read4() & mask`.
+//!
+//! Tee devices see every nibble written, whole, and are left out of reads,
+//! e.g. to log a port with a [`MockDevice`](crate::dev::mock::MockDevice)
+//! while the real devices run. Every device is ticked and connected.
+//!
+//! # Example
+//!
+//! ```
+//! use intel_4004::chips::{Rom4001, ShiftRegister4003};
+//! use intel_4004::dev::shared;
+//! use intel_4004::dev::splitter::PortSplitter;
+//! use intel_4004::dev::uart::{SoftUart, UartConfig};
+//!
+//! let uart = shared(SoftUart::new(UartConfig::new(110)));
+//! let sr = shared(ShiftRegister4003::new(10).with_clock_bit(1).with_data_bit(2));
+//! let mut rom = Rom4001::from_bytes(&[/* your ROM bytes */]);
+//! rom.attach_port(
+//!     PortSplitter::new()
+//!         .with_device(0b0001, uart.clone())
+//!         .with_device(0b0110, sr.clone()),
+//! );
+//! ```
+
+use crate::chips::Cpu4004;
+use crate::dev::{DeviceContext, IoDevice};
+
+#[derive(Default)]
+pub struct PortSplitter {
+    devices: Vec<(u8, Box<dyn IoDevice>)>,
+    tees: Vec<Box<dyn IoDevice>>,
+}
+
+impl PortSplitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wires the bits of `mask` to `dev`. Masks may overlap.
+    pub fn with_device(mut self, mask: u8, dev: impl IoDevice + 'static) -> Self {
+        self.devices.push((mask & 0xF, Box::new(dev)));
+        self
+    }
+
+    /// Adds a device that sees every write in full and never answers reads.
+    pub fn with_tee(mut self, dev: impl IoDevice + 'static) -> Self {
+        self.tees.push(Box::new(dev));
+        self
+    }
+
+    /// Bits owned by at least one device.
+    pub fn mask(&self) -> u8 {
+        self.devices.iter().fold(0, |m, (mask, _)| m | mask)
+    }
+}
+
+impl IoDevice for PortSplitter {
+    fn write4(&mut self, nibble: u8) {
+        for (mask, dev) in &mut self.devices {
+            dev.write4(nibble & *mask);
+        }
+        for tee in &mut self.tees {
+            tee.write4(nibble);
+        }
+    }
+
+    fn read4(&mut self) -> u8 {
+        self.devices
+            .iter_mut()
+            .fold(0, |value, (mask, dev)| value | (dev.read4() & *mask))
+    }
+
+    fn tick(&mut self, cpu: &Cpu4004) {
+        for (_, dev) in &mut self.devices {
+            dev.tick(cpu);
+        }
+        for tee in &mut self.tees {
+            tee.tick(cpu);
+        }
+    }
+
+    fn connect(&mut self, ctx: &DeviceContext) {
+        for (_, dev) in &mut self.devices {
+            dev.connect(ctx);
+        }
+        for tee in &mut self.tees {
+            tee.connect(ctx);
+        }
+    }
+}
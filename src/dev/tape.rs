@@ -0,0 +1,321 @@
+//! Sequential storage: paper tape or cassette.
+//!
+//! A [`TapeDevice`] attached to the ROM port reads and writes a tape one
+//! byte or one nibble at a time. The tape is kept in memory and, when opened
+//! with [`TapeDevice::open`], written through to a host file as the ROM
+//! writes, so the data survives the run.
+//!
+//! # Commands (write4 / WRR)
+//!
+//! ```text
+//! 1 hi lo   WRITE_BYTE    write a byte at the head, then advance
+//! 2         READ_BYTE     read a byte at the head, then advance
+//! 3 n       WRITE_NIBBLE  write one nibble at the head, then advance
+//! 4         READ_NIBBLE   read one nibble at the head, then advance
+//! 5         REWIND        move the head back to the start
+//! ```
+//!
+//! Writing in the middle of the tape overwrites it; writing at the end
+//! extends it. Nibble and byte operations can be mixed: the head moves one
+//! nibble at a time, high nibble of each byte first.
+//!
+//! # Status and data (read4 / RDR)
+//!
+//! Reads return a status first. When it has DATA set, the nibbles of the
+//! last read command follow (hi, lo for a byte), then a status again:
+//!
+//! ```text
+//! bit 0  READY   the tape accepts a command
+//! bit 1  EOF     the head is at the end of the tape
+//! bit 2  DATA    the next reads return data
+//! bit 3  ERROR   a host I/O error, or a command sent while not ready
+//! ```
+//!
+//! A read command dropped because the tape was busy, or one asking for more
+//! than is left on the tape, reads nothing: the status then has DATA clear.
+//!
+//! # Timing
+//!
+//! By default the tape is infinitely fast. [`TapeDevice::with_chars_per_second`]
+//! makes each operation take a character time in emulated cycles, e.g. 10 for
+//! a Teletype punch or 300 for a photoelectric reader, timed by the machine's
+//! [`Scheduler`](crate::scheduler::Scheduler). READY stays clear until the
+//! operation is done; commands sent meanwhile are dropped and set ERROR.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use intel_4004::chips::Rom4001;
+//! use intel_4004::dev::tape::TapeDevice;
+//!
+//! let mut rom = Rom4001::from_bytes(&[/* your ROM bytes */]);
+//! rom.attach_port(TapeDevice::open("log.tape").unwrap().with_chars_per_second(10));
+//! ```
+
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::dev::{DeviceContext, IoDevice};
+use crate::machine::CLOCK_HZ;
+use crate::scheduler::{Interval, Timer};
+
+pub const WRITE_BYTE: u8 = 0x1;
+pub const READ_BYTE: u8 = 0x2;
+pub const WRITE_NIBBLE: u8 = 0x3;
+pub const READ_NIBBLE: u8 = 0x4;
+pub const REWIND: u8 = 0x5;
+
+pub const READY: u8 = 0b0001;
+pub const EOF: u8 = 0b0010;
+pub const DATA: u8 = 0b0100;
+pub const ERROR: u8 = 0b1000;
+
+#[derive(Default)]
+enum State {
+    #[default]
+    Command,
+    /// Collecting the operands of a write; `drop` when it came in while busy.
+    Operand {
+        cmd: u8,
+        nibbles: Vec<u8>,
+        left: usize,
+        drop: bool,
+    },
+}
+
+#[derive(Default)]
+pub struct TapeDevice {
+    data: Vec<u8>,
+    file: Option<File>,
+    /// Head position, in nibbles.
+    head: usize,
+    state: State,
+    pending: VecDeque<u8>,
+    /// Set by a status with DATA: the next reads return `pending`.
+    in_data: bool,
+    char_time: Option<Interval>,
+    /// Registered with the machine's scheduler once connected.
+    timer: Option<Timer>,
+    overrun: bool,
+    error: Option<std::io::Error>,
+}
+
+impl TapeDevice {
+    /// An empty tape that lives in memory only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A tape in memory holding `bytes`, head at the start.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self {
+            data: bytes.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    /// Loads the tape from `path`, creating the file if needed. Writes go
+    /// straight through to it.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        Ok(Self {
+            data,
+            file: Some(file),
+            ..Self::default()
+        })
+    }
+
+    /// Makes every operation take one character time at `cps` characters per second.
+    pub fn with_chars_per_second(mut self, cps: u32) -> Self {
+        let cycles = CLOCK_HZ / cps.max(1) as u64;
+        self.char_time = Some(Interval::Cycles(cycles));
+        self
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Head position, in nibbles from the start of the tape.
+    pub fn position(&self) -> usize {
+        self.head
+    }
+
+    pub fn rewind(&mut self) {
+        self.head = 0;
+        self.pending.clear();
+        self.in_data = false;
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.head >= self.data.len() * 2
+    }
+
+    /// The last host I/O error, or else a command sent while the tape was
+    /// busy, as [`ErrorKind::ResourceBusy`]. ERROR stays set in the status
+    /// until every error is taken.
+    pub fn take_error(&mut self) -> Option<std::io::Error> {
+        if let Some(e) = self.error.take() {
+            return Some(e);
+        }
+        std::mem::take(&mut self.overrun).then(|| {
+            std::io::Error::new(
+                ErrorKind::ResourceBusy,
+                "command sent while the tape was busy",
+            )
+        })
+    }
+
+    fn is_ready(&self) -> bool {
+        self.timer.as_ref().is_none_or(Timer::is_due)
+    }
+
+    fn status(&self) -> u8 {
+        let mut status = 0;
+        if self.is_ready() {
+            status |= READY;
+        }
+        if self.is_at_end() {
+            status |= EOF;
+        }
+        if !self.pending.is_empty() {
+            status |= DATA;
+        }
+        if self.overrun || self.error.is_some() {
+            status |= ERROR;
+        }
+        status
+    }
+
+    fn read_nibble(&mut self) -> Option<u8> {
+        let byte = *self.data.get(self.head / 2)?;
+        let nibble = if self.head.is_multiple_of(2) {
+            byte >> 4
+        } else {
+            byte & 0xF
+        };
+        self.head += 1;
+        Some(nibble)
+    }
+
+    fn write_nibble(&mut self, nibble: u8) {
+        let i = self.head / 2;
+        if i == self.data.len() {
+            self.data.push(0);
+        }
+        let byte = &mut self.data[i];
+        *byte = if self.head.is_multiple_of(2) {
+            (nibble << 4) | (*byte & 0xF)
+        } else {
+            (*byte & 0xF0) | (nibble & 0xF)
+        };
+        self.head += 1;
+        let byte = *byte;
+        if let Some(file) = &mut self.file
+            && let Err(e) = file
+                .seek(SeekFrom::Start(i as u64))
+                .and_then(|_| file.write_all(&[byte]))
+        {
+            self.error = Some(e);
+        }
+    }
+
+    fn execute(&mut self, cmd: u8, operands: &[u8]) {
+        match cmd {
+            WRITE_BYTE | WRITE_NIBBLE => operands.iter().for_each(|&n| self.write_nibble(n)),
+            READ_BYTE | READ_NIBBLE => {
+                let n = if cmd == READ_BYTE { 2 } else { 1 };
+                if self.head + n <= self.data.len() * 2 {
+                    for _ in 0..n {
+                        let nibble = self.read_nibble().unwrap();
+                        self.pending.push_back(nibble);
+                    }
+                }
+            }
+            REWIND => self.rewind(),
+            _ => {}
+        }
+    }
+
+    fn begin(&mut self) -> bool {
+        if !self.is_ready() {
+            self.overrun = true;
+            return false;
+        }
+        if let Some(t) = &mut self.timer {
+            t.fire();
+        }
+        true
+    }
+}
+
+impl IoDevice for TapeDevice {
+    fn write4(&mut self, nibble: u8) {
+        let nibble = nibble & 0xF;
+        match std::mem::take(&mut self.state) {
+            State::Command => {
+                let left = match nibble {
+                    WRITE_BYTE => 2,
+                    WRITE_NIBBLE => 1,
+                    _ => 0,
+                };
+                let drop = !self.begin();
+                if left > 0 {
+                    let nibbles = Vec::with_capacity(left);
+                    self.state = State::Operand {
+                        cmd: nibble,
+                        nibbles,
+                        left,
+                        drop,
+                    };
+                } else if !drop {
+                    self.execute(nibble, &[]);
+                }
+            }
+            State::Operand {
+                cmd,
+                mut nibbles,
+                left,
+                drop,
+            } => {
+                nibbles.push(nibble);
+                if left > 1 {
+                    let left = left - 1;
+                    self.state = State::Operand {
+                        cmd,
+                        nibbles,
+                        left,
+                        drop,
+                    };
+                } else if !drop {
+                    self.execute(cmd, &nibbles);
+                }
+            }
+        }
+    }
+
+    fn read4(&mut self) -> u8 {
+        if self.in_data
+            && let Some(nibble) = self.pending.pop_front()
+        {
+            self.in_data = !self.pending.is_empty();
+            return nibble;
+        }
+        let status = self.status();
+        self.in_data = status & DATA != 0;
+        status
+    }
+
+    fn connect(&mut self, ctx: &DeviceContext) {
+        self.timer = self.char_time.map(|i| ctx.scheduler().register(i));
+    }
+}
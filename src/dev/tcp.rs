@@ -0,0 +1,248 @@
+//! TCP stream device.
+//!
+//! Connects out to a server or listens on a local port, and exposes the
+//! connection to the ROM as a byte stream. Sockets are non-blocking: reading
+//! with nothing to read, or writing to a slow peer, never stalls the machine.
+//!
+//! # Wire protocol (write4 / WRR)
+//!
+//! ```text
+//! nibble 0   high nibble of byte  ┐
+//! nibble 1   low nibble of byte   ┘  sent on the stream
+//! ```
+//!
+//! # Wire protocol (read4 / RDR)
+//!
+//! ```text
+//! read 0   status   bit 0  a byte is available
+//!                   bit 1  connected
+//!                   bit 2  closed by the peer
+//!                   bit 3  I/O error
+//! read 1   high nibble of byte  ┐  only after a status
+//! read 2   low nibble of byte   ┘  with bit 0 set
+//! ```
+//!
+//! A listening device accepts one connection at a time; once the peer closed
+//! it and every byte was read, it accepts the next one. Bytes still waiting
+//! to be sent to the old peer, and half-written bytes, are dropped then.
+//!
+//! Bytes the peer cannot take yet wait in a send buffer (4096 bytes by
+//! default, see [`TcpDevice::with_tx_capacity`]), also while a listening
+//! device has no peer. Once it is full, further bytes are dropped and set
+//! the error bit, with [`ErrorKind::WouldBlock`] from
+//! [`TcpDevice::take_error`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use intel_4004::chips::Rom4001;
+//! use intel_4004::dev::tcp::TcpDevice;
+//!
+//! let mut rom = Rom4001::from_bytes(&[/* your ROM bytes */]);
+//! rom.attach_port(TcpDevice::listen("127.0.0.1:4004").unwrap());
+//! ```
+
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+
+use crate::chips::Cpu4004;
+use crate::dev::IoDevice;
+use crate::dev::nibble::{ByteReader, ByteWriter};
+
+pub use crate::dev::nibble::AVAILABLE;
+pub const CONNECTED: u8 = 0b0010;
+pub const CLOSED: u8 = 0b0100;
+pub const ERROR: u8 = 0b1000;
+
+pub struct TcpDevice {
+    listener: Option<TcpListener>,
+    stream: Option<TcpStream>,
+    closed: bool,
+    error: Option<std::io::Error>,
+    out: ByteWriter,
+    tx: Vec<u8>,
+    tx_capacity: usize,
+    reader: ByteReader,
+    rx: VecDeque<u8>,
+}
+
+impl TcpDevice {
+    /// Connects to a server. Blocks until the connection is established.
+    pub fn connect(addr: impl ToSocketAddrs) -> std::io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Self::with(None, Some(stream)))
+    }
+
+    /// Listens on a local address, accepting a connection when a peer arrives.
+    pub fn listen(addr: impl ToSocketAddrs) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self::with(Some(listener), None))
+    }
+
+    fn with(listener: Option<TcpListener>, stream: Option<TcpStream>) -> Self {
+        Self {
+            listener,
+            stream,
+            closed: false,
+            error: None,
+            out: ByteWriter::default(),
+            tx: Vec::new(),
+            tx_capacity: 4096,
+            reader: ByteReader::default(),
+            rx: VecDeque::new(),
+        }
+    }
+
+    /// Sets how many bytes may wait to be sent before new ones are dropped.
+    pub fn with_tx_capacity(mut self, n: usize) -> Self {
+        self.tx_capacity = n;
+        self
+    }
+
+    /// The listening address, or the local end of the connection.
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        match (&self.listener, &self.stream) {
+            (Some(l), _) => l.local_addr(),
+            (None, Some(s)) => s.local_addr(),
+            (None, None) => Err(ErrorKind::NotConnected.into()),
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.stream.is_some() && !self.closed
+    }
+
+    /// The last I/O error, if any. It stays reported in the status until taken.
+    pub fn take_error(&mut self) -> Option<std::io::Error> {
+        self.error.take()
+    }
+
+    fn status(&self) -> u8 {
+        let mut status = 0;
+        if self.is_connected() {
+            status |= CONNECTED;
+        }
+        if self.closed {
+            status |= CLOSED;
+        }
+        if self.error.is_some() {
+            status |= ERROR;
+        }
+        status
+    }
+
+    fn poll(&mut self) {
+        if self.closed && self.rx.is_empty() && self.listener.is_some() {
+            self.stream = None;
+            self.closed = false;
+            // Nothing meant for the old peer goes to the next one.
+            self.tx.clear();
+            self.out = ByteWriter::default();
+            self.reader = ByteReader::default();
+        }
+        if self.stream.is_none() {
+            self.accept();
+        }
+        self.flush_tx();
+        self.fill_rx();
+    }
+
+    fn accept(&mut self) {
+        let Some(listener) = &self.listener else {
+            return;
+        };
+        match listener.accept() {
+            Ok((stream, _)) => {
+                if let Err(e) = stream.set_nonblocking(true) {
+                    self.error = Some(e);
+                    return;
+                }
+                let _ = stream.set_nodelay(true);
+                self.stream = Some(stream);
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    fn flush_tx(&mut self) {
+        let Some(stream) = &mut self.stream else {
+            return;
+        };
+        while !self.tx.is_empty() {
+            match stream.write(&self.tx) {
+                Ok(0) => break,
+                Ok(n) => {
+                    self.tx.drain(..n);
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    self.error = Some(e);
+                    self.closed = true;
+                    self.tx.clear();
+                }
+            }
+        }
+    }
+
+    fn fill_rx(&mut self) {
+        if self.closed {
+            return;
+        }
+        let Some(stream) = &mut self.stream else {
+            return;
+        };
+        let mut buf = [0u8; 256];
+        loop {
+            match stream.read(&mut buf) {
+                Ok(0) => {
+                    self.closed = true;
+                    break;
+                }
+                Ok(n) => self.rx.extend(&buf[..n]),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    self.error = Some(e);
+                    self.closed = true;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl IoDevice for TcpDevice {
+    fn write4(&mut self, nibble: u8) {
+        if let Some(byte) = self.out.push(nibble) {
+            if self.tx.len() >= self.tx_capacity {
+                self.error = Some(std::io::Error::new(
+                    ErrorKind::WouldBlock,
+                    "send buffer full, byte dropped",
+                ));
+                return;
+            }
+            self.tx.push(byte);
+            self.flush_tx();
+        }
+    }
+
+    fn read4(&mut self) -> u8 {
+        if self.reader.at_status() {
+            self.poll();
+        }
+        let status = self.status();
+        self.reader.read(&mut self.rx, status)
+    }
+
+    fn tick(&mut self, _cpu: &Cpu4004) {
+        if !self.tx.is_empty() {
+            self.flush_tx();
+        }
+    }
+}
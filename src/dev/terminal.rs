@@ -1,27 +1,191 @@
-use std::io::Write;
+//! Character terminal.
+//!
+//! # Output (write4 / WMP or WRR)
+//!
+//! ```text
+//! nibble 0   high nibble of byte  ┐
+//! nibble 1   low nibble of byte   ┘  printed as a Latin-1 char
+//! ```
+//!
+//! # Input (read4 / RDR)
+//!
+//! ```text
+//! read 0   status, bit 0 set when a byte is available
+//! read 1   high nibble of byte  ┐  only after a status
+//! read 2   low nibble of byte   ┘  with bit 0 set
+//! ```
+//!
+//! Output goes to stdout unless another sink is given with
+//! [`Terminal::with_sink`], UTF-8 encoded: bytes from 80H up take two bytes.
+//! Input comes from [`Terminal::push_input`], or from the host's stdin with
+//! [`Terminal::stdin`]. Attach the terminal to the ROM port to use both
+//! directions.
+//!
+//! # Example
+//!
+//! ```
+//! use intel_4004::chips::DataRam4002;
+//! use intel_4004::dev::terminal::{Capture, Terminal};
+//!
+//! let out = Capture::default();
+//! let mut ram = DataRam4002::default();
+//! ram.attach_port(Terminal::new().with_sink(out.clone()));
+//! // … run the machine …
+//! assert_eq!(out.text(), "");
+//! ```
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread::JoinHandle;
 
 use crate::dev::IoDevice;
+use crate::dev::nibble::{ByteReader, ByteWriter};
+
+pub use crate::dev::nibble::AVAILABLE;
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "arm",
+        target_arch = "aarch64",
+        target_arch = "riscv32",
+        target_arch = "riscv64"
+    )
+))]
+pub use crate::dev::tty::RawMode;
+
+/// Ctrl-D. Ends stdin input like end of file, since raw mode passes it on as a byte.
+const EOT: u8 = 0x04;
 
-#[derive(Default)]
 pub struct Terminal {
-    half: Option<u8>,
+    out: ByteWriter,
+    sink: Box<dyn Write>,
+    reader: ByteReader,
+    input: VecDeque<u8>,
+    stdin: Option<Receiver<u8>>,
+    reader_thread: Option<JoinHandle<()>>,
+    closed: bool,
+}
+
+impl Default for Terminal {
+    fn default() -> Self {
+        Self {
+            out: ByteWriter::default(),
+            sink: Box::new(std::io::stdout()),
+            reader: ByteReader::default(),
+            input: VecDeque::new(),
+            stdin: None,
+            reader_thread: None,
+            closed: false,
+        }
+    }
 }
 
 impl Terminal {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Sends printed characters to `sink` instead of stdout.
+    pub fn with_sink(mut self, sink: impl Write + 'static) -> Self {
+        self.sink = Box::new(sink);
+        self
+    }
+
+    /// Creates a terminal that also reads the host's stdin, without blocking the machine.
+    ///
+    /// Stdin is read on a background thread until end of file or Ctrl-D; the
+    /// thread is joined on the first read after that. Combine with [`RawMode`]
+    /// to get keys as they are typed instead of line by line.
+    pub fn stdin() -> Self {
+        let (tx, rx) = mpsc::channel();
+        let thread = std::thread::spawn(move || {
+            for byte in std::io::stdin().lock().bytes() {
+                let Ok(byte) = byte else { break };
+                if byte == EOT || tx.send(byte).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            stdin: Some(rx),
+            reader_thread: Some(thread),
+            ..Self::default()
+        }
+    }
+
+    /// Queues bytes for the ROM to read.
+    pub fn push_input(&mut self, bytes: &[u8]) {
+        self.input.extend(bytes);
+    }
+
+    /// Whether stdin has reached end of file and every byte from it was read.
+    pub fn is_closed(&self) -> bool {
+        self.closed && self.input.is_empty()
+    }
+
+    fn poll_stdin(&mut self) {
+        let Some(rx) = &self.stdin else { return };
+        loop {
+            match rx.try_recv() {
+                Ok(byte) => self.input.push_back(byte),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.closed = true;
+                    self.stdin = None;
+                    if let Some(thread) = self.reader_thread.take() {
+                        let _ = thread.join();
+                    }
+                    break;
+                }
+            }
+        }
+    }
 }
 
 impl IoDevice for Terminal {
     fn write4(&mut self, value4: u8) {
-        match self.half.take() {
-            None => self.half = Some(value4 & 0xF),
-            Some(hi) => {
-                let byte = (hi << 4) | (value4 & 0xF);
-                print!("{}", byte as char);
-                let _ = std::io::stdout().flush();
-            }
+        if let Some(byte) = self.out.push(value4) {
+            let _ = write!(self.sink, "{}", byte as char);
+            let _ = self.sink.flush();
         }
     }
+
+    fn read4(&mut self) -> u8 {
+        self.poll_stdin();
+        self.reader.read(&mut self.input, 0)
+    }
+}
+
+/// An in-memory sink that can be read while the terminal writes to it.
+#[derive(Clone, Default)]
+pub struct Capture(Rc<RefCell<Vec<u8>>>);
+
+impl Capture {
+    pub fn bytes(&self) -> Vec<u8> {
+        self.0.borrow().clone()
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+
+    pub fn clear(&self) {
+        self.0.borrow_mut().clear();
+    }
+}
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
 }
@@ -0,0 +1,265 @@
+//! Programmable interval timer and real-time clock.
+//!
+//! A [`TimerDevice`] on a ROM port counts emulated clock cycles and signals
+//! on the CPU's `TEST` pin when its period expires, so a ROM can wait with
+//! `JCN` instead of counting instructions. It also keeps a time of day that
+//! the ROM reads as BCD digits.
+//!
+//! # Commands (write4 / WRR)
+//!
+//! ```text
+//! 1 n×6   SET_PERIOD  period in cycles, 24 bits, high nibble first
+//! 2       START       restart the period, expiring every period
+//! 3       STOP
+//! 4       ACK         clear EXPIRED and OVERRUN, and raise TEST
+//! 5       READ_TIME   the next six reads return h h m m s s in BCD
+//! 6       ONE_SHOT    restart the period, expiring once
+//! 7 n×6   SET_TIME    h h m m s s in BCD
+//! ```
+//!
+//! # Status (read4 / RDR)
+//!
+//! ```text
+//! bit 0  EXPIRED   the period has expired since the last ACK
+//! bit 1  RUNNING
+//! bit 2  OVERRUN   it expired again before the ACK
+//! ```
+//!
+//! `TEST` idles high and is low while EXPIRED is set, so `JCN 9,*` (jump on
+//! TEST high) waits for the timer and `JCN 1` branches once it expired. Every
+//! expiry also raises INT, for hosts that poll it the way a 4040 would take
+//! an interrupt; [`Line::take`](crate::dev::Line::take) lowers it again.
+//! Both lines are the machine's, handed over in [`IoDevice::connect`].
+//!
+//! The clock runs on emulated time from midnight, or from
+//! [`TimerDevice::with_time`]. With [`TimerDevice::with_host_clock`] it
+//! follows the host's clock (UTC) instead. In both cases SET_TIME moves it.
+//!
+//! # Example
+//!
+//! ```
+//! use intel_4004::dev::timer::TimerDevice;
+//!
+//! let timer = TimerDevice::new().with_time(9, 30, 0);
+//! assert_eq!(timer.time(), (9, 30, 0));
+//! ```
+
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::chips::Cpu4004;
+use crate::dev::{DeviceContext, IoDevice, Line};
+use crate::machine::CLOCK_HZ;
+
+pub const SET_PERIOD: u8 = 0x1;
+pub const START: u8 = 0x2;
+pub const STOP: u8 = 0x3;
+pub const ACK: u8 = 0x4;
+pub const READ_TIME: u8 = 0x5;
+pub const ONE_SHOT: u8 = 0x6;
+pub const SET_TIME: u8 = 0x7;
+
+pub const EXPIRED: u8 = 0b0001;
+pub const RUNNING: u8 = 0b0010;
+pub const OVERRUN: u8 = 0b0100;
+
+const DAY: i64 = 24 * 60 * 60;
+
+#[derive(Default)]
+enum State {
+    #[default]
+    Command,
+    Operand {
+        cmd: u8,
+        nibbles: Vec<u8>,
+    },
+}
+
+pub struct TimerDevice {
+    /// TEST and INT, once connected to a machine.
+    lines: Option<(Line, Line)>,
+    state: State,
+    period: u64,
+    deadline: Option<u64>,
+    periodic: bool,
+    expired: bool,
+    overrun: bool,
+    host_clock: bool,
+    /// The time of day when `set_cycle` was reached; an offset to the host's
+    /// clock with `host_clock`.
+    offset: i64,
+    set_cycle: u64,
+    pending: VecDeque<u8>,
+    now: u64,
+}
+
+impl TimerDevice {
+    /// A stopped timer with the clock at midnight.
+    pub fn new() -> Self {
+        Self {
+            lines: None,
+            state: State::Command,
+            period: 0,
+            deadline: None,
+            periodic: false,
+            expired: false,
+            overrun: false,
+            host_clock: false,
+            offset: 0,
+            set_cycle: 0,
+            pending: VecDeque::new(),
+            now: 0,
+        }
+    }
+
+    /// Starts the emulated clock at `h:m:s`.
+    pub fn with_time(mut self, h: u8, m: u8, s: u8) -> Self {
+        self.set_time(h, m, s);
+        self
+    }
+
+    /// Follows the host's clock, in UTC.
+    pub fn with_host_clock(mut self) -> Self {
+        self.host_clock = true;
+        self.offset = 0;
+        self
+    }
+
+    /// The period in cycles, 0 until the ROM sets one.
+    pub fn period(&self) -> u64 {
+        self.period
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.deadline.is_some()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expired
+    }
+
+    /// The time of day as hours, minutes and seconds.
+    pub fn time(&self) -> (u8, u8, u8) {
+        let t = (self.offset + self.elapsed()).rem_euclid(DAY);
+        ((t / 3600) as u8, (t / 60 % 60) as u8, (t % 60) as u8)
+    }
+
+    pub fn set_time(&mut self, h: u8, m: u8, s: u8) {
+        let t = h as i64 * 3600 + m as i64 * 60 + s as i64;
+        self.set_cycle = self.now;
+        self.offset = t - self.elapsed();
+    }
+
+    /// Seconds since the clock was set, or since the Unix epoch on the host's clock.
+    fn elapsed(&self) -> i64 {
+        if self.host_clock {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs() as i64)
+        } else {
+            (self.now.saturating_sub(self.set_cycle) / CLOCK_HZ) as i64
+        }
+    }
+
+    fn start(&mut self, periodic: bool) {
+        if self.period > 0 {
+            self.deadline = Some(self.now + self.period);
+            self.periodic = periodic;
+        }
+    }
+
+    fn execute(&mut self, cmd: u8, nibbles: &[u8]) {
+        match cmd {
+            SET_PERIOD => self.period = nibbles.iter().fold(0, |p, &n| p << 4 | n as u64),
+            START => self.start(true),
+            ONE_SHOT => self.start(false),
+            STOP => self.deadline = None,
+            ACK => {
+                self.expired = false;
+                self.overrun = false;
+                if let Some((test, _)) = &self.lines {
+                    test.set(true);
+                }
+            }
+            READ_TIME => {
+                let (h, m, s) = self.time();
+                for v in [h, m, s] {
+                    self.pending.extend([v / 10, v % 10]);
+                }
+            }
+            SET_TIME => {
+                let bcd = |i: usize| nibbles[i] * 10 + nibbles[i + 1];
+                self.set_time(bcd(0) % 24, bcd(2) % 60, bcd(4) % 60);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Default for TimerDevice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IoDevice for TimerDevice {
+    fn write4(&mut self, nibble: u8) {
+        let nibble = nibble & 0xF;
+        match std::mem::take(&mut self.state) {
+            State::Command if matches!(nibble, SET_PERIOD | SET_TIME) => {
+                let nibbles = Vec::with_capacity(6);
+                self.state = State::Operand {
+                    cmd: nibble,
+                    nibbles,
+                };
+            }
+            State::Command => self.execute(nibble, &[]),
+            State::Operand { cmd, mut nibbles } => {
+                nibbles.push(nibble);
+                if nibbles.len() < 6 {
+                    self.state = State::Operand { cmd, nibbles };
+                } else {
+                    self.execute(cmd, &nibbles);
+                }
+            }
+        }
+    }
+
+    fn read4(&mut self) -> u8 {
+        if let Some(nibble) = self.pending.pop_front() {
+            return nibble;
+        }
+        let mut status = 0;
+        if self.expired {
+            status |= EXPIRED;
+        }
+        if self.is_running() {
+            status |= RUNNING;
+        }
+        if self.overrun {
+            status |= OVERRUN;
+        }
+        status
+    }
+
+    fn tick(&mut self, cpu: &Cpu4004) {
+        self.now = cpu.cycles();
+        let mut fired = false;
+        while let Some(deadline) = self.deadline
+            && deadline <= self.now
+        {
+            self.overrun |= self.expired;
+            self.expired = true;
+            self.deadline = self.periodic.then_some(deadline + self.period);
+            fired = true;
+        }
+        if fired && let Some((test, int)) = &self.lines {
+            test.set(false);
+            int.set(true);
+        }
+    }
+
+    fn connect(&mut self, ctx: &DeviceContext) {
+        self.lines = Some((ctx.test_line(), ctx.int_line()));
+    }
+}
@@ -0,0 +1,105 @@
+//! Terminal settings through libc's termios, for the PTY device and
+//! [`RawMode`].
+
+use std::ffi::c_int;
+use std::io::{self, Stdin};
+use std::os::fd::AsRawFd;
+
+const TCSANOW: c_int = 0;
+const ICANON: u32 = 0o2;
+const ECHO: u32 = 0o10;
+const VTIME: usize = 5;
+const VMIN: usize = 6;
+
+/// `struct termios` as glibc and musl lay it out on the targets `tty` is
+/// built for. Other architectures, e.g. MIPS and PowerPC, differ.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub(crate) struct Termios {
+    iflag: u32,
+    oflag: u32,
+    cflag: u32,
+    lflag: u32,
+    line: u8,
+    cc: [u8; 32],
+    ispeed: u32,
+    ospeed: u32,
+}
+
+// glibc's `sizeof(struct termios)` and `_Alignof(struct termios)` there.
+const _: () = assert!(size_of::<Termios>() == 60 && align_of::<Termios>() == 4);
+
+unsafe extern "C" {
+    fn tcgetattr(fd: c_int, termios: *mut Termios) -> c_int;
+    fn tcsetattr(fd: c_int, action: c_int, termios: *const Termios) -> c_int;
+    fn cfmakeraw(termios: *mut Termios);
+}
+
+impl Termios {
+    /// The settings of terminal `fd`; fails if it is not a terminal.
+    pub(crate) fn get(fd: &impl AsRawFd) -> io::Result<Self> {
+        let mut termios = Self {
+            iflag: 0,
+            oflag: 0,
+            cflag: 0,
+            lflag: 0,
+            line: 0,
+            cc: [0; 32],
+            ispeed: 0,
+            ospeed: 0,
+        };
+        // SAFETY: `termios` is a `struct termios` that outlives the call.
+        if unsafe { tcgetattr(fd.as_raw_fd(), &mut termios) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(termios)
+    }
+
+    /// Applies these settings to terminal `fd` at once.
+    pub(crate) fn set(&self, fd: &impl AsRawFd) -> io::Result<()> {
+        // SAFETY: `self` is a `struct termios` filled by `tcgetattr`.
+        if unsafe { tcsetattr(fd.as_raw_fd(), TCSANOW, self) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Bytes pass through unchanged: no echo, line editing, signals or
+    /// newline translation.
+    pub(crate) fn make_raw(&mut self) {
+        // SAFETY: `self` is a valid `struct termios`.
+        unsafe { cfmakeraw(self) }
+    }
+
+    /// Keys are read as they are typed, without echo; Ctrl-C and output
+    /// processing keep working.
+    fn make_cbreak(&mut self) {
+        self.lflag &= !(ICANON | ECHO);
+        self.cc[VMIN] = 1;
+        self.cc[VTIME] = 0;
+    }
+}
+
+/// Puts the host terminal in non-canonical, no-echo mode until dropped.
+pub struct RawMode {
+    stdin: Stdin,
+    saved: Termios,
+}
+
+impl RawMode {
+    /// Fails when stdin is not a terminal, e.g. piped from a file.
+    pub fn enable() -> io::Result<Self> {
+        let stdin = io::stdin();
+        let saved = Termios::get(&stdin)?;
+        let mut cbreak = saved;
+        cbreak.make_cbreak();
+        cbreak.set(&stdin)?;
+        Ok(Self { stdin, saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = self.saved.set(&self.stdin);
+    }
+}
@@ -0,0 +1,336 @@
+//! Bit-banged serial line.
+//!
+//! [`SoftUart`] sits on a `Rom4001` or `DataRam4002` port and plays the
+//! other end of a serial line that the ROM drives in software: it decodes the
+//! characters the ROM sends by toggling one output bit, and sends characters
+//! back on one input bit or on the CPU's `TEST` pin.
+//!
+//! Timing is measured in emulated clock cycles ([`Machine::cycles`]), so a
+//! ROM's delay loops give the same baud rate whatever the host's speed.
+//!
+//! ```text
+//! transmit (WRR / WMP)   bit tx_bit: idle high, start 0, data LSB first,
+//!                        optional parity, stop bits high
+//! receive (RDR or TEST)  bit rx_bit: the same framing, sent by the device
+//! ```
+//!
+//! A character queued with [`SoftUart::send`] starts on the ROM's next
+//! sample of the line, so a ROM that polls for the start bit never misses
+//! the first one; characters after it follow back to back.
+//!
+//! # Example
+//!
+//! ```
+//! use intel_4004::chips::Rom4001;
+//! use intel_4004::dev::shared;
+//! use intel_4004::dev::uart::{Parity, SoftUart, UartConfig};
+//!
+//! let config = UartConfig::new(300).with_format(7, Parity::Even, 1);
+//! let uart = shared(SoftUart::new(config).with_tx_bit(0).with_rx_bit(3));
+//! let mut rom = Rom4001::from_bytes(&[/* your ROM bytes */]);
+//! rom.attach_port(uart.clone());
+//! uart.borrow_mut().send(b"hello");
+//! // … run the machine, then uart.borrow_mut().recv() …
+//! ```
+//!
+//! [`Machine::cycles`]: crate::machine::Machine::cycles
+
+use std::collections::VecDeque;
+
+use crate::chips::Cpu4004;
+use crate::dev::{IoDevice, Line};
+use crate::machine::CLOCK_HZ;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Parity {
+    None,
+    Even,
+    Odd,
+}
+
+/// A receive error, reported instead of the byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UartError {
+    /// A stop bit was sampled low.
+    Framing,
+    Parity,
+}
+
+/// Line speed and character format. Defaults to 8N1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UartConfig {
+    pub cycles_per_bit: u64,
+    pub data_bits: u8,
+    pub parity: Parity,
+    pub stop_bits: u8,
+}
+
+impl UartConfig {
+    /// 8N1 at `baud`, on the nominal 740 kHz clock.
+    pub fn new(baud: u32) -> Self {
+        Self::with_cycles_per_bit(CLOCK_HZ / baud.max(1) as u64)
+    }
+
+    /// 8N1 with a bit time of `cycles` clock cycles.
+    pub fn with_cycles_per_bit(cycles: u64) -> Self {
+        Self {
+            cycles_per_bit: cycles.max(1),
+            data_bits: 8,
+            parity: Parity::None,
+            stop_bits: 1,
+        }
+    }
+
+    pub fn with_format(mut self, data_bits: u8, parity: Parity, stop_bits: u8) -> Self {
+        self.data_bits = data_bits.clamp(5, 8);
+        self.parity = parity;
+        self.stop_bits = stop_bits.clamp(1, 2);
+        self
+    }
+
+    /// Bits on the line per character: start, data, parity, stop.
+    fn frame_bits(&self) -> u64 {
+        1 + self.data_bits as u64 + (self.parity != Parity::None) as u64 + self.stop_bits as u64
+    }
+
+    fn parity_bit(&self, byte: u8) -> bool {
+        let ones = (byte & self.data_mask()).count_ones();
+        match self.parity {
+            Parity::Even => !ones.is_multiple_of(2),
+            Parity::Odd => ones.is_multiple_of(2),
+            Parity::None => true,
+        }
+    }
+
+    fn data_mask(&self) -> u8 {
+        (0xFFu16 >> (8 - self.data_bits)) as u8
+    }
+
+    /// Line level of bit `n` of the frame carrying `byte`.
+    fn level(&self, byte: u8, n: u64) -> bool {
+        let data = self.data_bits as u64;
+        match n {
+            0 => false,
+            n if n <= data => byte & (1 << (n - 1)) != 0,
+            n if n == data + 1 && self.parity != Parity::None => self.parity_bit(byte),
+            _ => true,
+        }
+    }
+}
+
+/// Turns the levels a ROM drives on a line into characters.
+///
+/// A falling edge on the idle (high) line starts a frame; each following bit
+/// is sampled in the middle of its bit time.
+pub(crate) struct Decoder {
+    config: UartConfig,
+    level: bool,
+    frame: Option<(u64, u64, u8, bool)>, // start cycle, next bit, data, parity ok
+    received: VecDeque<Result<u8, UartError>>,
+    errors: u64,
+}
+
+impl Decoder {
+    pub fn new(config: UartConfig) -> Self {
+        Self {
+            config,
+            level: true,
+            frame: None,
+            received: VecDeque::new(),
+            errors: 0,
+        }
+    }
+
+    /// The line changes to `level` at cycle `now`.
+    pub fn set_level(&mut self, level: bool, now: u64) {
+        self.advance(now);
+        if self.frame.is_none() && self.level && !level {
+            self.frame = Some((now, 1, 0, true));
+        }
+        self.level = level;
+    }
+
+    /// Samples every bit whose middle is at or before `now`.
+    pub fn advance(&mut self, now: u64) {
+        let cfg = self.config;
+        let data_bits = cfg.data_bits as u64;
+        while let Some((start, bit, data, parity_ok)) = self.frame {
+            let at = start + bit * cfg.cycles_per_bit + cfg.cycles_per_bit / 2;
+            if at > now {
+                break;
+            }
+            let level = self.level;
+            self.frame = if bit <= data_bits {
+                let data = data | ((level as u8) << (bit - 1));
+                Some((start, bit + 1, data, parity_ok))
+            } else if bit == data_bits + 1 && cfg.parity != Parity::None {
+                let parity_ok = level == cfg.parity_bit(data);
+                Some((start, bit + 1, data, parity_ok))
+            } else if !level {
+                self.errors += 1;
+                self.received.push_back(Err(UartError::Framing));
+                None
+            } else if bit + 1 < cfg.frame_bits() {
+                Some((start, bit + 1, data, parity_ok))
+            } else {
+                if !parity_ok {
+                    self.errors += 1;
+                }
+                self.received.push_back(if parity_ok {
+                    Ok(data)
+                } else {
+                    Err(UartError::Parity)
+                });
+                None
+            };
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Result<u8, UartError>> {
+        self.received.pop_front()
+    }
+
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+}
+
+/// Drives a line with queued characters, for a ROM to sample.
+pub(crate) struct Encoder {
+    config: UartConfig,
+    queue: VecDeque<u8>,
+    frame: Option<(u8, u64)>, // byte, start cycle
+}
+
+impl Encoder {
+    pub fn new(config: UartConfig) -> Self {
+        Self {
+            config,
+            queue: VecDeque::new(),
+            frame: None,
+        }
+    }
+
+    pub fn push(&mut self, byte: u8) {
+        self.queue.push_back(byte);
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.frame.is_none() && self.queue.is_empty()
+    }
+
+    /// Finishes the characters sent by cycle `now`. The next queued one
+    /// follows without a gap, as a real sender's would.
+    pub fn advance(&mut self, now: u64) {
+        let cfg = self.config;
+        while let Some((_, start)) = self.frame {
+            let end = start + cfg.frame_bits() * cfg.cycles_per_bit;
+            if end > now {
+                break;
+            }
+            self.frame = self.queue.pop_front().map(|next| (next, end));
+        }
+    }
+
+    /// Line level at cycle `now`. A queued character starts now if the line is idle.
+    pub fn level(&mut self, now: u64) -> bool {
+        self.advance(now);
+        if self.frame.is_none() {
+            self.frame = self.queue.pop_front().map(|next| (next, now));
+        }
+        match self.frame {
+            Some((byte, start)) => self
+                .config
+                .level(byte, (now - start) / self.config.cycles_per_bit),
+            None => true,
+        }
+    }
+}
+
+/// The far end of a serial line bit-banged by the ROM.
+pub struct SoftUart {
+    decoder: Decoder,
+    encoder: Encoder,
+    tx_bit: u8,
+    rx_bit: u8,
+    test: Option<Line>,
+    now: u64,
+}
+
+impl SoftUart {
+    /// A UART listening on bit 0 of port writes and answering on bit 0 of port reads.
+    pub fn new(config: UartConfig) -> Self {
+        Self {
+            decoder: Decoder::new(config),
+            encoder: Encoder::new(config),
+            tx_bit: 0,
+            rx_bit: 0,
+            test: None,
+            now: 0,
+        }
+    }
+
+    /// The port bit the ROM transmits on.
+    pub fn with_tx_bit(mut self, bit: u8) -> Self {
+        self.tx_bit = bit & 3;
+        self
+    }
+
+    /// The port bit the ROM receives on.
+    pub fn with_rx_bit(mut self, bit: u8) -> Self {
+        self.rx_bit = bit & 3;
+        self
+    }
+
+    /// Also drives the receive line on `test`, e.g. [`Machine::test_line`],
+    /// for ROMs that sample it with `JCN`. Useful on RAM ports, which have no input.
+    ///
+    /// [`Machine::test_line`]: crate::machine::Machine::test_line
+    pub fn with_test(mut self, test: Line) -> Self {
+        test.set(true);
+        self.test = Some(test);
+        self
+    }
+
+    /// Queues characters for the ROM to receive.
+    pub fn send(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.encoder.push(byte);
+        }
+    }
+
+    /// Whether every queued character has been sent.
+    pub fn is_idle(&self) -> bool {
+        self.encoder.is_idle()
+    }
+
+    /// The next character the ROM sent, or the error it was received with.
+    pub fn recv(&mut self) -> Option<Result<u8, UartError>> {
+        self.decoder.pop()
+    }
+
+    /// Characters received so far with a framing or parity error.
+    pub fn errors(&self) -> u64 {
+        self.decoder.errors()
+    }
+}
+
+impl IoDevice for SoftUart {
+    fn write4(&mut self, nibble: u8) {
+        self.decoder
+            .set_level(nibble & (1 << self.tx_bit) != 0, self.now);
+    }
+
+    fn read4(&mut self) -> u8 {
+        (self.encoder.level(self.now) as u8) << self.rx_bit
+    }
+
+    fn tick(&mut self, cpu: &Cpu4004) {
+        self.now = cpu.cycles();
+        self.decoder.advance(self.now);
+        self.encoder.advance(self.now);
+        if let Some(test) = &self.test {
+            test.set(self.encoder.level(self.now));
+        }
+    }
+}
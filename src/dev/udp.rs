@@ -1,20 +1,54 @@
 //! UDP datagram device.
 //!
 //! Attaches to the ROM I/O port (`WRR`) and sends a UDP datagram each time
-//! the 4004 finishes writing a complete message.
+//! the 4004 finishes writing a complete message. Datagrams received from the
+//! remote address are queued, and the ROM takes them one at a time with the
+//! receive command and reads them back through `RDR`.
 //!
 //! # Wire protocol (write4 / WRR)
 //!
 //! ```text
 //! nibble 0       length high nibble  ┐
-//! nibble 1       length low nibble   ┘ byte count (0–255)
+//! nibble 1       length low nibble   ┘ byte count (1–255)
 //! nibble 2,3     first byte (hi, lo)
 //! nibble 4,5     second byte …
 //! …
 //! ```
 //!
 //! The device fires `send()` after the last byte arrives and resets for the
-//! next message automatically.
+//! next message automatically. A length of 0 sends nothing: it is the
+//! receive command, which takes the next queued datagram for the ROM to read.
+//!
+//! With an interval set, complete messages wait in a send queue and go out
+//! one per interval as the machine runs, timed by the machine's
+//! [`Scheduler`](crate::scheduler::Scheduler); the ROM is never blocked.
+//! Nothing is dropped: once the queue holds its capacity (16 by default) the
+//! status read sets [`FULL`], and a ROM should wait for it to clear before
+//! writing the next message. A device that is not in a machine has no clock
+//! and sends at once.
+//!
+//! # Wire protocol (read4 / RDR)
+//!
+//! ```text
+//! read 0         status, bit 0 set when a datagram is queued,
+//!                bit 1 set while the send queue is full,
+//!                bit 2 set when the datagram taken by the receive command follows,
+//!                bit 3 set when an error is pending (see take_error)
+//! read 1         length high nibble  ┐ only after a status
+//! read 2         length low nibble   ┘ with bit 2 set
+//! read 3,4       first byte (hi, lo)
+//! …
+//! ```
+//!
+//! Reading the status changes nothing, so a ROM can poll it for [`FULL`]
+//! without receiving. Once the last nibble of a datagram is read the next
+//! read is a status again; a receive command drops whatever is left unread.
+//! Received datagrams wait in a bounded queue (16 by default); when it is
+//! full, new datagrams are dropped. Payloads longer than 255 bytes are
+//! truncated.
+//!
+//! Send and receive errors never reach the ROM as a panic: they are counted
+//! in [`UdpDevice::stats`] and the last one is kept for [`UdpDevice::take_error`].
 //!
 //! # Example
 //!
@@ -26,74 +60,294 @@
 //! rom.attach_port(UdpDevice::new("0.0.0.0:0", "127.0.0.1:1234").unwrap());
 //! ```
 
-use crate::dev::IoDevice;
-use std::net::UdpSocket;
+use crate::chips::Cpu4004;
+pub use crate::dev::nibble::AVAILABLE;
+use crate::dev::{DeviceContext, IoDevice};
+use crate::scheduler::{Interval, Timer};
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
 use std::time::Duration;
 
+pub const FULL: u8 = 0b0010;
+pub const DATA: u8 = 0b0100;
+pub const ERROR: u8 = 0b1000;
+
 enum State {
     WaitLenHi,
-    WaitLenLo { hi: u8 },
-    Data { bytes_left: usize, hi: Option<u8>, buf: Vec<u8> },
+    WaitLenLo {
+        hi: u8,
+    },
+    Data {
+        bytes_left: usize,
+        hi: Option<u8>,
+        buf: Vec<u8>,
+    },
+}
+
+enum RxState {
+    Status,
+    LenHi { buf: Vec<u8> },
+    LenLo { buf: Vec<u8> },
+    Data { buf: Vec<u8>, pos: usize, lo: bool },
+}
+
+/// Frame counters, since the device was created.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UdpStats {
+    pub sent: u64,
+    /// Frames the socket refused to send.
+    pub failed: u64,
+    pub received: u64,
+    /// Datagrams dropped because the receive queue was full.
+    pub rx_dropped: u64,
 }
 
 pub struct UdpDevice {
-    socket:        UdpSocket,
-    state:         State,
-    /// Optional pause after each send. Useful to throttle a looping ROM.
-    send_interval: Option<Duration>,
+    socket: UdpSocket,
+    state: State,
+    /// Optional spacing between sends. Useful to throttle a looping ROM.
+    interval: Option<Interval>,
+    /// Registered with the machine's scheduler once connected.
+    timer: Option<Timer>,
+    tx_queue: VecDeque<Vec<u8>>,
+    tx_capacity: usize,
+    rx_state: RxState,
+    /// Taken by the receive command, read out after the next status.
+    received: Option<Vec<u8>>,
+    rx_queue: VecDeque<Vec<u8>>,
+    rx_capacity: usize,
+    stats: UdpStats,
+    error: Option<std::io::Error>,
 }
 
 impl UdpDevice {
     pub fn new(local_addr: &str, remote_addr: &str) -> std::io::Result<Self> {
         let socket = UdpSocket::bind(local_addr)?;
         socket.connect(remote_addr)?;
+        socket.set_nonblocking(true)?;
         Ok(Self {
             socket,
-            state:         State::WaitLenHi,
-            send_interval: None,
+            state: State::WaitLenHi,
+            interval: None,
+            timer: None,
+            tx_queue: VecDeque::new(),
+            tx_capacity: 16,
+            rx_state: RxState::Status,
+            received: None,
+            rx_queue: VecDeque::new(),
+            rx_capacity: 16,
+            stats: UdpStats::default(),
+            error: None,
         })
     }
 
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Sends at most one message per `d` of host time.
     pub fn with_interval(mut self, d: Duration) -> Self {
-        self.send_interval = Some(d);
+        self.interval = Some(Interval::Host(d));
+        self
+    }
+
+    /// Sends at most one message per `cycles` emulated clock cycles.
+    pub fn with_cycle_interval(mut self, cycles: u64) -> Self {
+        self.interval = Some(Interval::Cycles(cycles));
+        self
+    }
+
+    /// Sets how many throttled messages may wait before the status reports [`FULL`].
+    pub fn with_tx_capacity(mut self, n: usize) -> Self {
+        self.tx_capacity = n;
+        self
+    }
+
+    /// Number of messages waiting to be sent.
+    pub fn pending(&self) -> usize {
+        self.tx_queue.len()
+    }
+
+    /// Sets how many received datagrams wait for the ROM before new ones are dropped.
+    pub fn with_rx_capacity(mut self, n: usize) -> Self {
+        self.rx_capacity = n;
         self
     }
+
+    pub fn stats(&self) -> UdpStats {
+        self.stats
+    }
+
+    /// The last send or receive error, if any. It stays flagged in the status until taken.
+    pub fn take_error(&mut self) -> Option<std::io::Error> {
+        self.error.take()
+    }
+
+    /// Sends queued messages, as many as the timer allows right now.
+    fn flush_tx(&mut self) {
+        while !self.tx_queue.is_empty() {
+            if let Some(t) = &mut self.timer {
+                if !t.is_due() {
+                    break;
+                }
+                t.fire();
+            }
+            let buf = self.tx_queue.pop_front().unwrap();
+            self.send_frame(&buf);
+        }
+    }
+
+    fn send_frame(&mut self, buf: &[u8]) {
+        match self.socket.send(buf) {
+            Ok(_) => self.stats.sent += 1,
+            Err(e) => {
+                self.stats.failed += 1;
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// The receive command: takes the next queued datagram, if any.
+    fn receive(&mut self) {
+        self.poll_rx();
+        self.rx_state = RxState::Status;
+        self.received = self.rx_queue.pop_front();
+    }
+
+    /// Moves datagrams waiting on the socket into the receive queue.
+    fn poll_rx(&mut self) {
+        let mut buf = [0u8; 255];
+        loop {
+            match self.socket.recv(&mut buf) {
+                Ok(n) if self.rx_queue.len() < self.rx_capacity => {
+                    self.stats.received += 1;
+                    self.rx_queue.push_back(buf[..n].to_vec());
+                }
+                Ok(_) => self.stats.rx_dropped += 1,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    // e.g. ECONNREFUSED left by an ICMP reply to an earlier send
+                    self.error = Some(e);
+                    break;
+                }
+            }
+        }
+    }
 }
 
 impl IoDevice for UdpDevice {
     fn read4(&mut self) -> u8 {
-        0
+        let (next, nibble) = match std::mem::replace(&mut self.rx_state, RxState::Status) {
+            RxState::Status => {
+                self.poll_rx();
+                let mut status = if self.error.is_some() { ERROR } else { 0 };
+                if !self.rx_queue.is_empty() {
+                    status |= AVAILABLE;
+                }
+                if self.tx_queue.len() >= self.tx_capacity.max(1) {
+                    status |= FULL;
+                }
+                match self.received.take() {
+                    Some(buf) => (RxState::LenHi { buf }, status | DATA),
+                    None => (RxState::Status, status),
+                }
+            }
+            RxState::LenHi { buf } => {
+                let hi = (buf.len() >> 4) as u8;
+                (RxState::LenLo { buf }, hi)
+            }
+            RxState::LenLo { buf } => {
+                let lo = (buf.len() & 0xF) as u8;
+                if buf.is_empty() {
+                    (RxState::Status, lo)
+                } else {
+                    (
+                        RxState::Data {
+                            buf,
+                            pos: 0,
+                            lo: false,
+                        },
+                        lo,
+                    )
+                }
+            }
+            RxState::Data { buf, pos, lo } => {
+                let byte = buf[pos];
+                match (lo, pos + 1 == buf.len()) {
+                    (false, _) => (RxState::Data { buf, pos, lo: true }, byte >> 4),
+                    (true, true) => (RxState::Status, byte & 0xF),
+                    (true, false) => (
+                        RxState::Data {
+                            buf,
+                            pos: pos + 1,
+                            lo: false,
+                        },
+                        byte & 0xF,
+                    ),
+                }
+            }
+        };
+        self.rx_state = next;
+        nibble
     }
 
     fn write4(&mut self, nibble: u8) {
-        match &mut self.state {
+        let frame = match &mut self.state {
             State::WaitLenHi => {
                 self.state = State::WaitLenLo { hi: nibble };
+                None
             }
             State::WaitLenLo { hi } => {
                 let len = ((*hi as usize) << 4) | (nibble as usize);
-                self.state = State::Data {
-                    bytes_left: len,
-                    hi:         None,
-                    buf:        Vec::with_capacity(len),
-                };
+                if len == 0 {
+                    self.state = State::WaitLenHi;
+                    self.receive();
+                    None
+                } else {
+                    self.state = State::Data {
+                        bytes_left: len,
+                        hi: None,
+                        buf: Vec::with_capacity(len),
+                    };
+                    None
+                }
             }
-            State::Data { bytes_left, hi, buf } => {
-                match hi.take() {
-                    None    => *hi = Some(nibble),
-                    Some(h) => {
-                        buf.push((h << 4) | nibble);
-                        *bytes_left -= 1;
-                        if *bytes_left == 0 {
-                            let _ = self.socket.send(buf);
-                            if let Some(d) = self.send_interval {
-                                std::thread::sleep(d);
-                            }
-                            self.state = State::WaitLenHi;
-                        }
+            State::Data {
+                bytes_left,
+                hi,
+                buf,
+            } => match hi.take() {
+                None => {
+                    *hi = Some(nibble);
+                    None
+                }
+                Some(h) => {
+                    buf.push((h << 4) | nibble);
+                    *bytes_left -= 1;
+                    if *bytes_left == 0 {
+                        let buf = std::mem::take(buf);
+                        self.state = State::WaitLenHi;
+                        Some(buf)
+                    } else {
+                        None
                     }
                 }
-            }
+            },
+        };
+        if let Some(buf) = frame {
+            self.tx_queue.push_back(buf);
+            self.flush_tx();
+        }
+    }
+
+    fn tick(&mut self, _cpu: &Cpu4004) {
+        if !self.tx_queue.is_empty() {
+            self.flush_tx();
         }
     }
+
+    fn connect(&mut self, ctx: &DeviceContext) {
+        self.timer = self.interval.map(|i| ctx.scheduler().register(i));
+    }
 }
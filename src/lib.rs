@@ -1,6 +1,8 @@
 pub mod bus;
 pub mod chips;
+pub mod debugger;
 pub mod dev;
 pub mod disasm;
 pub mod isa;
 pub mod machine;
+pub mod scheduler;
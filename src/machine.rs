@@ -1,23 +1,56 @@
 use crate::bus::Bus;
 use crate::chips::Cpu4004;
+use crate::dev::{DeviceContext, Line};
+use crate::scheduler::Scheduler;
+
+/// Nominal 4004 clock frequency; [`Machine::cycles`] counts periods of this clock.
+pub const CLOCK_HZ: u64 = 740_000;
 
 pub struct Machine<B: Bus> {
     cpu: Cpu4004,
     bus: B,
+    test: Line,
+    int: Line,
+    stop: Line,
+    scheduler: Scheduler,
 }
 
 impl<B: Bus> Machine<B> {
+    /// Builds the machine and connects the devices already on `bus`; devices
+    /// attached later through [`Machine::bus_mut`] are connected as they come.
     pub fn new(bus: B) -> Self {
-        Self {
+        let mut m = Self {
             cpu: Cpu4004::default(),
             bus,
-        }
+            test: Line::new(true),
+            int: Line::new(false),
+            stop: Line::new(false),
+            scheduler: Scheduler::new(),
+        };
+        m.bus.connect(&m.context());
+        m
+    }
+
+    /// The lines devices are handed, not tied to any port.
+    pub fn context(&self) -> DeviceContext {
+        DeviceContext::new(self.test.clone(), self.int.clone(), self.stop.clone())
+            .with_scheduler(self.scheduler.clone())
+    }
+
+    /// The clock device timers run on; it follows [`Machine::cycles`].
+    pub fn scheduler(&self) -> &Scheduler {
+        &self.scheduler
     }
 
     pub fn cpu(&self) -> &Cpu4004 {
         &self.cpu
     }
 
+    /// For debuggers, to edit registers between steps.
+    pub fn cpu_mut(&mut self) -> &mut Cpu4004 {
+        &mut self.cpu
+    }
+
     pub fn bus(&self) -> &B {
         &self.bus
     }
@@ -30,19 +63,50 @@ impl<B: Bus> Machine<B> {
         self.cpu.cycles()
     }
 
+    /// The CPU's TEST pin. Idle high; hand a clone to the device driving it.
+    pub fn test_line(&self) -> Line {
+        self.test.clone()
+    }
+
+    /// The INT line. Idle low; the 4004 has no interrupt input, so the host
+    /// polls it, e.g. between [`Machine::run_until`] calls.
+    pub fn int_line(&self) -> Line {
+        self.int.clone()
+    }
+
+    /// Whether a device asked to stop since the last run ended.
+    pub fn stop_requested(&self) -> bool {
+        self.stop.get()
+    }
+
     pub fn step(&mut self) {
+        self.cpu.set_test(self.test.get());
         self.cpu.step(&mut self.bus);
+        self.scheduler.advance(self.cpu.cycles());
+        self.bus.tick(&self.cpu);
     }
 
-    pub fn run_steps(&mut self, n: usize) {
+    /// Runs `n` instructions, or fewer if a device requests a stop. Returns
+    /// whether it stopped early, clearing the request.
+    pub fn run_steps(&mut self, n: usize) -> bool {
         for _ in 0..n {
-            self.cpu.step(&mut self.bus);
+            self.step();
+            if self.stop.get() {
+                return self.stop.take();
+            }
         }
+        false
     }
 
-    pub fn run_until(&mut self, mut stop: impl FnMut(&Cpu4004) -> bool) {
+    /// Runs until `stop` holds or a device requests a stop. Returns whether a
+    /// device stopped it, clearing the request.
+    pub fn run_until(&mut self, mut stop: impl FnMut(&Cpu4004) -> bool) -> bool {
         while !stop(&self.cpu) {
-            self.cpu.step(&mut self.bus);
+            self.step();
+            if self.stop.get() {
+                return self.stop.take();
+            }
         }
+        false
     }
 }
@@ -1,10 +1,55 @@
+use std::process::ExitCode;
+
 use intel_4004::bus::simple::SimpleBus;
 use intel_4004::chips::{DataRam4002, Rom4001};
+use intel_4004::debugger::{Debugger, gdb, repl};
 use intel_4004::dev::terminal::Terminal;
 use intel_4004::disasm::disassemble;
 use intel_4004::machine::Machine;
 
-fn main() {
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().collect();
+    let debugger = |path: &str| {
+        let rom = Rom4001::from_file(path).map_err(|e| format!("{path}: {e}"))?;
+        let machine = Machine::new(SimpleBus::new(rom, DataRam4002::default()));
+        Ok::<_, String>(Debugger::new(machine))
+    };
+    let result = match (args.get(1).map(String::as_str), args.get(2)) {
+        // `debug ROM.bin`: the ROM on a 4001 with one 4002, under the REPL.
+        (Some("debug"), Some(path)) => debugger(path).and_then(|mut dbg| {
+            let (stdin, stdout) = (std::io::stdin().lock(), std::io::stdout());
+            repl::run(&mut dbg, stdin, stdout).map_err(|e| e.to_string())
+        }),
+        // `gdb ROM.bin [ADDR]`: the same, served over the GDB remote serial
+        // protocol to one client that speaks it directly.
+        (Some("gdb"), Some(path)) => debugger(path).and_then(|mut dbg| {
+            let addr = args.get(3).map_or("127.0.0.1:1234", String::as_str);
+            eprintln!("waiting for a remote serial protocol client on {addr}");
+            eprintln!("(stock gdb has no 4004 target and will not work)");
+            gdb::listen(&mut dbg, addr).map_err(|e| e.to_string())
+        }),
+        (Some("debug"), None) => Err(format!("usage: {} debug ROM.bin", args[0])),
+        (Some("gdb"), None) => Err(format!(
+            "usage: {} gdb ROM.bin [ADDR]\n\
+             serves the ROM over the GDB remote serial protocol, to a client that\n\
+             speaks it directly; stock gdb has no 4004 target and will not work",
+            args[0]
+        )),
+        _ => {
+            demo();
+            Ok(())
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn demo() {
     // let rom = Rom::from_bytes(&[
     //     0xD0, // LDM 0
     //     0xF2, // IAC
@@ -0,0 +1,135 @@
+//! Device timing, kept by the machine.
+//!
+//! A [`Machine`](crate::machine::Machine) owns one [`Scheduler`] and moves
+//! its clock forward after every instruction. Devices find it in their
+//! [`DeviceContext`](crate::dev::DeviceContext) and register a [`Timer`] for
+//! each rate they must keep. Instead of sleeping, a device queues its work
+//! and lets it out from `tick` whenever its timer is due, so the machine and
+//! every other device keep running in the meantime.
+//!
+//! # Example
+//!
+//! ```
+//! use intel_4004::scheduler::{Interval, Scheduler};
+//!
+//! let sched = Scheduler::new();
+//! let mut timer = sched.register(Interval::Cycles(100));
+//! assert!(timer.is_due());
+//! timer.fire();
+//! sched.advance(99);
+//! assert!(!timer.is_due());
+//! sched.advance(100);
+//! assert!(timer.is_due());
+//! ```
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Minimum spacing between two events, in emulated clock cycles or host time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interval {
+    Cycles(u64),
+    Host(Duration),
+}
+
+#[derive(Debug)]
+struct Slot {
+    interval: Interval,
+    next_cycle: u64,
+    next_instant: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    now: u64,
+    /// Indexed by timer id; `None` once the timer is dropped.
+    slots: Vec<Option<Slot>>,
+}
+
+/// The machine's clock and the timers registered against it. Clones share
+/// the same state.
+#[derive(Clone, Debug, Default)]
+pub struct Scheduler(Rc<RefCell<Inner>>);
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The emulated cycle count the machine last reported.
+    pub fn now(&self) -> u64 {
+        self.0.borrow().now
+    }
+
+    /// Moves the clock to cycle `now`. The machine does this after every instruction.
+    pub fn advance(&self, now: u64) {
+        self.0.borrow_mut().now = now;
+    }
+
+    /// Adds a timer that is due at once, then once per `interval` after each
+    /// [`Timer::fire`].
+    pub fn register(&self, interval: Interval) -> Timer {
+        let slot = Slot {
+            interval,
+            next_cycle: 0,
+            next_instant: None,
+        };
+        let mut inner = self.0.borrow_mut();
+        let id = match inner.slots.iter().position(Option::is_none) {
+            Some(id) => {
+                inner.slots[id] = Some(slot);
+                id
+            }
+            None => {
+                inner.slots.push(Some(slot));
+                inner.slots.len() - 1
+            }
+        };
+        Timer {
+            sched: self.clone(),
+            id,
+        }
+    }
+
+    /// Number of timers registered and not yet dropped.
+    pub fn timers(&self) -> usize {
+        self.0.borrow().slots.iter().flatten().count()
+    }
+}
+
+/// A device's registration with the [`Scheduler`]. Unregisters when dropped.
+#[derive(Debug)]
+pub struct Timer {
+    sched: Scheduler,
+    id: usize,
+}
+
+impl Timer {
+    /// Whether an event may happen now.
+    pub fn is_due(&self) -> bool {
+        let inner = self.sched.0.borrow();
+        let slot = inner.slots[self.id].as_ref().expect("timer is registered");
+        match slot.interval {
+            Interval::Cycles(_) => inner.now >= slot.next_cycle,
+            Interval::Host(_) => slot.next_instant.is_none_or(|t| Instant::now() >= t),
+        }
+    }
+
+    /// Records an event now, holding the next one back by the interval.
+    pub fn fire(&mut self) {
+        let mut inner = self.sched.0.borrow_mut();
+        let now = inner.now;
+        let slot = inner.slots[self.id].as_mut().expect("timer is registered");
+        match slot.interval {
+            Interval::Cycles(n) => slot.next_cycle = now + n,
+            Interval::Host(d) => slot.next_instant = Some(Instant::now() + d),
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.sched.0.borrow_mut().slots[self.id] = None;
+    }
+}
@@ -0,0 +1,24 @@
+//! Fixtures shared by the integration tests. Each test crate uses only some.
+#![allow(dead_code)]
+
+use intel_4004::bus::simple::SimpleBus;
+use intel_4004::chips::{DataRam4002, Rom4001};
+use intel_4004::debugger::Debugger;
+use intel_4004::dev::{IoDevice, Shared};
+use intel_4004::machine::Machine;
+
+/// A machine running `bytes`, with `dev` on the ROM port.
+pub fn machine<D: IoDevice + 'static>(dev: &Shared<D>, bytes: &[u8]) -> Machine<SimpleBus> {
+    let mut rom = Rom4001::from_bytes(bytes);
+    rom.attach_port(dev.clone());
+    Machine::new(SimpleBus::new(rom, DataRam4002::default()))
+}
+
+// 000: FIM P0,53H; SRC P0; LDM 7; WRM; RDM; JMS 010H; JUN 006H ... 010: IAC; BBL 0
+pub fn debugger() -> Debugger<SimpleBus> {
+    let mut bytes = vec![0u8; 0x12];
+    bytes[..10].copy_from_slice(&[0x20, 0x53, 0x21, 0xD7, 0xE0, 0xE9, 0x50, 0x10, 0x40, 0x06]);
+    bytes[0x10..].copy_from_slice(&[0xF2, 0xC0]);
+    let rom = Rom4001::from_bytes(&bytes);
+    Debugger::new(Machine::new(SimpleBus::new(rom, DataRam4002::default())))
+}
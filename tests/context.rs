@@ -0,0 +1,96 @@
+use intel_4004::bus::simple::SimpleBus;
+use intel_4004::bus::standard::StandardBus;
+use intel_4004::chips::{Cpu4004, DataRam4002, Mem4289, Rom4001};
+use intel_4004::dev::splitter::PortSplitter;
+use intel_4004::dev::{DeviceContext, IoDevice, PortId, Shared, shared};
+use intel_4004::machine::Machine;
+
+/// Drives TEST and INT from the bits written, stops the machine on 8 and
+/// counts the cycles it has been ticked.
+#[derive(Default)]
+struct Probe {
+    ctx: Option<DeviceContext>,
+    cycles: u64,
+}
+
+impl IoDevice for Probe {
+    fn write4(&mut self, nibble: u8) {
+        let ctx = self.ctx.as_ref().expect("connected");
+        ctx.test_line().set(nibble & 1 != 0);
+        ctx.int_line().set(nibble & 2 != 0);
+        if nibble & 8 != 0 {
+            ctx.request_stop();
+        }
+    }
+
+    fn tick(&mut self, cpu: &Cpu4004) {
+        self.cycles = cpu.cycles();
+    }
+
+    fn connect(&mut self, ctx: &DeviceContext) {
+        self.ctx = Some(ctx.clone());
+    }
+}
+
+fn port(probe: &Shared<Probe>) -> Option<PortId> {
+    probe.borrow().ctx.as_ref().and_then(|c| c.port())
+}
+
+fn machine(bytes: &[u8], probe: &Shared<Probe>) -> Machine<SimpleBus> {
+    let mut rom = Rom4001::from_bytes(bytes);
+    rom.attach_port(probe.clone());
+    Machine::new(SimpleBus::new(rom, DataRam4002::default()))
+}
+
+#[test]
+fn devices_learn_their_port() {
+    let (rom, ram) = (shared(Probe::default()), shared(Probe::default()));
+    let mut m = machine(&[], &rom);
+    assert_eq!(port(&rom), Some(PortId::Rom));
+    // Attached after the machine was built.
+    m.bus_mut().data.attach_port(ram.clone());
+    assert_eq!(port(&ram), Some(PortId::Ram));
+
+    let (io3, shared_io) = (shared(Probe::default()), shared(Probe::default()));
+    let mut mem = Mem4289::from_bytes(&[]);
+    mem.attach_port(3, io3.clone());
+    mem.attach_port(
+        5,
+        PortSplitter::new().with_device(0b0001, shared_io.clone()),
+    );
+    let _m = Machine::new(StandardBus::new(mem, DataRam4002::default()));
+    assert_eq!(port(&io3), Some(PortId::Io(3)));
+    assert_eq!(port(&shared_io), Some(PortId::Io(5)));
+    assert_eq!(PortId::Io(0xB).to_string(), "IOB");
+}
+
+#[test]
+fn devices_drive_test_and_int() {
+    let probe = shared(Probe::default());
+    // LDM 2; WRR; JCN TEST=0 0; LDM 1; WRR
+    let mut m = machine(&[0xD2, 0xE2, 0x11, 0x00, 0xD1, 0xE2], &probe);
+    m.run_steps(2);
+    assert!(!m.test_line().get());
+    assert!(m.int_line().get());
+    m.run_steps(1);
+    assert_eq!(m.cpu().pc(), 0, "TEST low: jumped back");
+    m.run_steps(2);
+    assert_eq!(probe.borrow().cycles, 48);
+}
+
+#[test]
+fn devices_stop_the_machine() {
+    let probe = shared(Probe::default());
+    // LDM 8; WRR; NOP...
+    let mut m = machine(&[0xD8, 0xE2], &probe);
+    assert!(m.run_steps(10));
+    assert_eq!(m.cpu().pc(), 2);
+    assert!(!m.stop_requested(), "cleared");
+    assert!(!m.run_steps(3));
+    assert_eq!(m.cpu().pc(), 5);
+
+    m.context().request_stop();
+    assert!(m.stop_requested());
+    assert!(m.run_until(|_| false));
+    assert_eq!(m.cpu().pc(), 6);
+}
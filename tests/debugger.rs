@@ -0,0 +1,203 @@
+use intel_4004::bus::simple::SimpleBus;
+use intel_4004::bus::standard::StandardBus;
+use intel_4004::chips::{DataRam4002, Mem4289, Rom4001};
+use intel_4004::debugger::{
+    Cell, Debugger, Direction, PortAccess, RamAccess, RamLocation, StopReason, Watch,
+};
+use intel_4004::dev::PortId;
+use intel_4004::dev::mock::MockDevice;
+use intel_4004::isa::Instruction;
+use intel_4004::machine::Machine;
+
+fn debugger(bytes: &[u8]) -> Debugger<SimpleBus> {
+    let mut rom = Rom4001::from_bytes(bytes);
+    rom.attach_port(MockDevice::new().with_default_read(0x9).expect_any_write());
+    Debugger::new(Machine::new(SimpleBus::new(rom, DataRam4002::default())))
+}
+
+// 000: FIM P0,53H; SRC P0; LDM 7; WRM; RDM; WR2; RD2; LDM 1; DCL; WRR; RDR; WMP
+const RAM_AND_PORTS: &[u8] = &[
+    0x20, 0x53, 0x21, 0xD7, 0xE0, 0xE9, 0xE6, 0xEE, 0xD1, 0xFD, 0xE2, 0xEA, 0xE1,
+];
+
+#[test]
+fn pc_breakpoints_count_hits() {
+    // 000: IAC; JUN 000H
+    let mut dbg = debugger(&[0xF2, 0x40, 0x00]);
+    let every = dbg.break_at(0x000);
+    let sixth = dbg.break_if(0x000, |cpu| cpu.acc() == 6);
+    dbg.set_ignore_count(every, 2);
+    assert_eq!(dbg.run(100), StopReason::Breakpoint { id: every, pc: 0 });
+    assert_eq!(dbg.machine().cpu().acc(), 2, "third time on PC 000H");
+    assert_eq!(dbg.hits(every), 3);
+
+    dbg.set_enabled(every, false);
+    assert_eq!(dbg.run(100), StopReason::Breakpoint { id: sixth, pc: 0 });
+    assert_eq!((dbg.hits(every), dbg.hits(sixth)), (3, 1));
+    assert_eq!(dbg.breakpoints()[1].what, "PC 000H if condition");
+
+    assert_eq!(dbg.step(), StopReason::Step);
+    assert_eq!(dbg.machine().cpu().pc(), 1);
+    assert!(dbg.remove(sixth));
+    assert!(!dbg.remove(sixth));
+    assert_eq!(dbg.run(10), StopReason::StepLimit);
+}
+
+#[test]
+fn ram_watchpoints_report_the_access() {
+    let mut dbg = debugger(RAM_AND_PORTS);
+    let location = RamLocation {
+        bank: 0,
+        chip: 1,
+        reg: 1,
+        cell: Cell::Char(3),
+    };
+    let write = dbg.watch_ram(location, Watch::Write);
+    let status = dbg.watch_register(0, 1, 1, Watch::Read);
+    assert_eq!(
+        dbg.run(100),
+        StopReason::Ram {
+            id: write,
+            access: RamAccess {
+                location,
+                direction: Direction::Write,
+                value: 7,
+                pc: 0x004,
+            },
+        }
+    );
+    let StopReason::Ram { id, access } = dbg.run(100) else {
+        panic!("RDM not caught");
+    };
+    assert_eq!((id, access.pc, access.value), (status, 0x005, 7));
+    let StopReason::Ram { access, .. } = dbg.run(100) else {
+        panic!("RD2 not caught");
+    };
+    assert_eq!(access.pc, 0x007, "WR2 is not a read");
+    assert_eq!(access.location.cell, Cell::Status(2));
+    assert_eq!(
+        dbg.breakpoints()[0].what,
+        "watch write bank 0 chip 1 reg 1 char 3"
+    );
+}
+
+#[test]
+fn instruction_and_port_breakpoints() {
+    let mut dbg = debugger(RAM_AND_PORTS);
+    let dcl = dbg.break_on_mnemonic("dcl");
+    let rdr = dbg.break_on(|i| *i == Instruction::Rdr);
+    let ports = dbg.watch_port(PortId::Rom, Watch::Any);
+    let wmp = dbg.watch_port(PortId::Ram, Watch::Write);
+    assert_eq!(
+        dbg.run(100),
+        StopReason::Instruction {
+            id: dcl,
+            pc: 0x009,
+            instr: Instruction::Dcl,
+        }
+    );
+    assert_eq!(dbg.machine().bus().data.bank(), 0, "DCL not run yet");
+    let access = |port, direction, value, pc| PortAccess {
+        port,
+        direction,
+        value,
+        pc,
+    };
+    assert_eq!(
+        dbg.run(100),
+        StopReason::Port {
+            id: ports,
+            access: access(PortId::Rom, Direction::Write, 1, 0x00A),
+        }
+    );
+    assert_eq!(dbg.machine().bus().data.bank(), 1);
+    assert!(matches!(dbg.run(100), StopReason::Instruction { id, .. } if id == rdr));
+    assert_eq!(
+        dbg.run(100),
+        StopReason::Port {
+            id: ports,
+            access: access(PortId::Rom, Direction::Read, 9, 0x00B),
+        }
+    );
+    assert_eq!(
+        dbg.run(100),
+        StopReason::Port {
+            id: wmp,
+            access: access(PortId::Ram, Direction::Write, 9, 0x00C),
+        }
+    );
+}
+
+#[test]
+fn port_watchpoints_tell_4289_ports_apart() {
+    // 000: FIM P0,30H; SRC P0; LDM 1; WRR; FIM P0,50H; SRC P0; WRR; RDR
+    let mut mem =
+        Mem4289::from_bytes(&[0x20, 0x30, 0x21, 0xD1, 0xE2, 0x20, 0x50, 0x21, 0xE2, 0xEA]);
+    mem.attach_port(
+        5,
+        MockDevice::new().with_default_read(0x9).expect_any_write(),
+    );
+    let mut dbg = Debugger::new(Machine::new(StandardBus::new(mem, DataRam4002::default())));
+    let rom = dbg.watch_port(PortId::Rom, Watch::Any);
+    let io5 = dbg.watch_port(PortId::Io(5), Watch::Read);
+    let io3 = dbg.watch_port(PortId::Io(3), Watch::Write);
+    assert_eq!(dbg.breakpoints()[2].what, "watch write IO3 port");
+    let access = |port, direction, value, pc| PortAccess {
+        port,
+        direction,
+        value,
+        pc,
+    };
+    assert_eq!(
+        dbg.run(100),
+        StopReason::Port {
+            id: io3,
+            access: access(PortId::Io(3), Direction::Write, 1, 0x004),
+        }
+    );
+    assert_eq!(
+        dbg.run(100),
+        StopReason::Port {
+            id: io5,
+            access: access(PortId::Io(5), Direction::Read, 9, 0x009),
+        }
+    );
+    assert_eq!((dbg.hits(rom), dbg.hits(io3)), (0, 1));
+}
+
+#[test]
+fn devices_stop_the_debugger() {
+    let mut dbg = debugger(RAM_AND_PORTS);
+    dbg.machine().context().request_stop();
+    assert_eq!(dbg.run(100), StopReason::Device);
+    assert_eq!(dbg.machine().cpu().pc(), 2);
+}
+
+#[test]
+fn next_and_finish_follow_calls() {
+    // 000: JMS 010H; JUN 000H ... 010: IAC; JMS 020H; BBL 0 ... 020: IAC; BBL 0
+    let mut rom = vec![0u8; 0x22];
+    rom[..4].copy_from_slice(&[0x50, 0x10, 0x40, 0x00]);
+    rom[0x10..0x14].copy_from_slice(&[0xF2, 0x50, 0x20, 0xC0]);
+    rom[0x20..0x22].copy_from_slice(&[0xF2, 0xC0]);
+    let mut dbg = debugger(&rom);
+    assert_eq!(dbg.next(100), StopReason::Step);
+    assert_eq!(dbg.machine().cpu().pc(), 0x002);
+    assert_eq!(dbg.machine().cpu().acc(), 0, "BBL 0");
+    assert!(dbg.breakpoints().is_empty());
+
+    dbg.run(3); // JUN; JMS; IAC
+    assert_eq!(dbg.next(100), StopReason::Step);
+    assert_eq!(dbg.machine().cpu().pc(), 0x013);
+    dbg.step(); // BBL
+    dbg.run(3); // JUN; JMS; IAC
+    dbg.step(); // JMS 020H
+    assert_eq!(dbg.machine().cpu().stack(), ([0x002, 0x013, 0x000], 2));
+    assert_eq!(dbg.finish(100), Some(StopReason::Step));
+    assert_eq!(dbg.machine().cpu().pc(), 0x013);
+    assert_eq!(dbg.finish(100), Some(StopReason::Step));
+    assert_eq!(dbg.machine().cpu().pc(), 0x002);
+    assert_eq!(dbg.finish(100), None, "back in the main program");
+    assert_eq!(dbg.machine().cpu().pc(), 0x002);
+    assert_eq!(dbg.break_at(0x010), 1, "no IDs used up");
+}
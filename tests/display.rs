@@ -0,0 +1,50 @@
+use intel_4004::bus::simple::SimpleBus;
+use intel_4004::chips::{DataRam4002, Rom4001};
+use intel_4004::dev::display::{Mode, SegmentDisplay};
+use intel_4004::dev::{Shared, shared};
+use intel_4004::machine::Machine;
+
+fn machine(display: &Shared<SegmentDisplay>, bytes: &[u8]) -> Machine<SimpleBus> {
+    let mut ram = DataRam4002::default();
+    ram.attach_port(display.clone());
+    Machine::new(SimpleBus::new(Rom4001::from_bytes(bytes), ram))
+}
+
+#[rustfmt::skip]
+const SCAN_12: &[u8] = &[
+    0xD0, 0xE1, 0xD1, 0xE1, // digit 0 ← 1
+    0xD1, 0xE1, 0xD2, 0xE1, // digit 1 ← 2
+    0x40, 0x00,             // JUN 000H
+];
+
+#[test]
+fn bcd_digits_scanned_in_a_loop() {
+    let display = shared(SegmentDisplay::new(3, Mode::Bcd));
+    let mut m = machine(&display, SCAN_12);
+    m.run_steps(1000);
+    assert_eq!(display.borrow().text(), "12 ");
+}
+
+#[test]
+fn digits_fade_when_no_longer_scanned() {
+    let display = shared(SegmentDisplay::new(2, Mode::Bcd).with_persistence(500));
+    let mut bytes = SCAN_12[..8].to_vec();
+    bytes.extend([0x40, 0x08]); // JUN 008H
+    let mut m = machine(&display, &bytes);
+
+    m.run_until(|cpu| cpu.cycles() >= 400);
+    assert_eq!(display.borrow().text(), "12");
+    m.run_until(|cpu| cpu.cycles() >= 600);
+    assert_eq!(display.borrow().text(), "  ");
+}
+
+#[test]
+fn raw_segments_with_decimal_point() {
+    let display = shared(SegmentDisplay::new(1, Mode::Segments));
+    // digit 0 ← 0x86: dp + segments b, c ('1.')
+    let mut m = machine(&display, &[0xD0, 0xE1, 0xD8, 0xE1, 0xD6, 0xE1]);
+    m.run_steps(6);
+    let display = display.borrow();
+    assert_eq!(display.text(), "1.");
+    assert_eq!(display.render(), "    \n  | \n  |.");
+}
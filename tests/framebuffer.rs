@@ -0,0 +1,72 @@
+mod common;
+
+use intel_4004::dev::IoDevice;
+use intel_4004::dev::framebuffer::Framebuffer;
+use intel_4004::dev::shared;
+
+use common::machine;
+
+/// `LDM n; WRR` for each nibble.
+fn writes(nibbles: &[u8]) -> Vec<u8> {
+    nibbles.iter().flat_map(|&n| [0xD0 | n, 0xE2]).collect()
+}
+
+#[test]
+fn rom_plots_and_presents_a_frame() {
+    let fb = shared(Framebuffer::new(12, 3));
+    // ROW 01; COLUMN 02; PLOT F; PLOT 9; PRESENT; SET (at column 10); COLUMN 02; RDR; XCH R0
+    let mut rom = writes(&[2, 0, 1, 1, 0, 2, 3, 0xF, 3, 9, 7, 4, 1, 0, 2]);
+    rom.extend([0xEA, 0xB0]);
+    let mut m = machine(&fb, &rom);
+    m.run_steps(32);
+    let fb = fb.borrow();
+    assert_eq!(fb.presented(), "............\n..#####..#..\n............\n");
+    assert_eq!(fb.render(), "............\n..#####..##.\n............\n");
+    assert_eq!(fb.frames(), 1);
+    assert_eq!(m.cpu().reg(0), 0xF);
+}
+
+#[test]
+fn clips_and_clears() {
+    let mut fb = Framebuffer::new(6, 2);
+    for n in [1, 0, 4, 3, 0xF, 2, 0, 9, 4] {
+        fb.write4(n); // COLUMN 04; PLOT F; ROW 09 (outside); SET
+    }
+    assert_eq!(fb.render(), "....##\n......\n");
+    assert!(fb.pixel(5, 0) && !fb.pixel(6, 0) && !fb.pixel(0, 9));
+    fb.write4(6);
+    assert_eq!(fb.render(), "......\n......\n");
+    assert_eq!(Framebuffer::new(1000, 0).width(), 256);
+}
+
+#[test]
+fn writes_ppm_and_png() {
+    let mut fb = Framebuffer::new(3, 2);
+    fb.set_pixel(1, 0, true);
+    let ppm = fb.to_ppm(2);
+    assert!(ppm.starts_with(b"P6\n6 4\n255\n"));
+    assert_eq!(ppm.len(), 11 + 6 * 4 * 3);
+    assert_eq!(
+        ppm[11..11 + 18],
+        [
+            0, 0, 0, 0, 0, 0, 255, 255, 255, 255, 255, 255, 0, 0, 0, 0, 0, 0
+        ]
+    );
+
+    let png = fb.to_png(1);
+    assert_eq!(png[..8], *b"\x89PNG\r\n\x1a\n");
+    assert_eq!(png[12..16], *b"IHDR");
+    assert_eq!(png[16..24], [0, 0, 0, 3, 0, 0, 0, 2]);
+    // IDAT: zlib header, one final stored block of two 4-byte rows, Adler-32.
+    let idat = &png[33..];
+    assert_eq!(idat[..4], [0, 0, 0, 19]);
+    assert_eq!(idat[4..8], *b"IDAT");
+    assert_eq!(idat[8..15], [0x78, 0x01, 1, 8, 0, 0xF7, 0xFF]);
+    assert_eq!(idat[15..23], [0, 0, 255, 0, 0, 0, 0, 0]);
+    assert_eq!(idat[23..27], [0x06, 0x02, 0x01, 0x00]);
+    // IEND with its well-known CRC.
+    assert_eq!(
+        png[png.len() - 12..],
+        [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]
+    );
+}
@@ -0,0 +1,173 @@
+mod common;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use intel_4004::bus::simple::SimpleBus;
+use intel_4004::debugger::{Debugger, gdb};
+
+use common::debugger;
+
+struct Client {
+    writer: TcpStream,
+    reader: BufReader<TcpStream>,
+}
+
+impl Client {
+    fn send(&mut self, data: impl AsRef<[u8]>) {
+        let data = data.as_ref();
+        let sum = data.iter().fold(0u8, |s, &b| s.wrapping_add(b));
+        self.writer.write_all(b"$").unwrap();
+        self.writer.write_all(data).unwrap();
+        write!(self.writer, "#{sum:02x}").unwrap();
+        let mut ack = [0];
+        self.reader.read_exact(&mut ack).unwrap();
+        assert_eq!(ack[0], b'+', "ack for {}", String::from_utf8_lossy(data));
+    }
+
+    fn reply(&mut self) -> String {
+        let mut junk = Vec::new();
+        self.reader.read_until(b'$', &mut junk).unwrap();
+        let mut data = Vec::new();
+        self.reader.read_until(b'#', &mut data).unwrap();
+        data.pop();
+        let mut sum = [0; 2];
+        self.reader.read_exact(&mut sum).unwrap();
+        let expected = data.iter().fold(0u8, |s, &b| s.wrapping_add(b));
+        assert_eq!(
+            std::str::from_utf8(&sum).unwrap(),
+            format!("{expected:02x}")
+        );
+        self.writer.write_all(b"+").unwrap();
+        String::from_utf8(data).unwrap()
+    }
+
+    fn request(&mut self, data: impl AsRef<[u8]>) -> String {
+        self.send(data);
+        self.reply()
+    }
+}
+
+/// Runs `script` as a GDB client against the stub serving `dbg`.
+fn session(dbg: &mut Debugger<SimpleBus>, script: impl FnOnce(&mut Client) + Send + 'static) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let client = thread::spawn(move || {
+        let stream = TcpStream::connect(addr).unwrap();
+        stream.set_nodelay(true).unwrap();
+        let mut client = Client {
+            writer: stream.try_clone().unwrap(),
+            reader: BufReader::new(stream),
+        };
+        script(&mut client);
+        client.send("k");
+    });
+    let (stream, _) = listener.accept().unwrap();
+    gdb::serve(dbg, stream).unwrap();
+    client.join().unwrap();
+}
+
+#[test]
+fn registers_and_memory() {
+    let mut dbg = debugger();
+    session(&mut dbg, |gdb| {
+        assert!(
+            gdb.request("qSupported:multiprocess+")
+                .contains("qXfer:features:read+")
+        );
+        let xml = gdb.request("qXfer:features:read:target.xml:0,1000");
+        assert!(xml.starts_with("l<?xml"));
+        assert!(xml.contains("<reg name=\"r15\" bitsize=\"8\""));
+        assert!(
+            gdb.request("qXfer:features:read:target.xml:0,10")
+                .starts_with('m')
+        );
+        assert_eq!(gdb.request("?"), "S05");
+
+        assert_eq!(gdb.request("g"), "00".repeat(27));
+        assert_eq!(gdb.request("P2=05"), "OK");
+        assert_eq!(gdb.request("P12=1100"), "OK");
+        assert_eq!(gdb.request("p12"), "1100");
+        let regs = gdb.request("g");
+        assert_eq!(&regs[4..6], "05", "r0");
+        assert_eq!(
+            gdb.request(format!("G{}", regs.replace("1100", "0100"))),
+            "OK"
+        );
+        assert_eq!(gdb.request("p12"), "0100");
+
+        assert_eq!(gdb.request("m0,4"), "205321d7");
+        assert_eq!(gdb.request("M10053,2:090a"), "OK");
+        assert_eq!(gdb.request("M2001e,1:04"), "OK");
+        assert_eq!(gdb.request("M10,1:f3"), "OK");
+        assert_eq!(
+            gdb.request("m107ff,4"),
+            "00",
+            "reads stop at the end of RAM"
+        );
+        assert_eq!(gdb.request("m30000,1"), "E14");
+        assert_eq!(gdb.request("vMustReplyEmpty"), "");
+    });
+    let m = dbg.machine();
+    assert_eq!((m.cpu().reg(0), m.cpu().pc()), (5, 0x001));
+    let ram = &m.bus().data;
+    assert_eq!(ram.character(0, 1, 1, 3), 0x9);
+    assert_eq!(ram.character(0, 1, 1, 4), 0xA);
+    assert_eq!(ram.status(0, 1, 3, 2), 0x4);
+    assert_eq!(m.bus().prog.read_byte(0x10), 0xF3);
+}
+
+#[test]
+fn binary_and_malformed_packets() {
+    let mut dbg = debugger();
+    session(&mut dbg, |gdb| {
+        assert_eq!(gdb.request(b"\x80"), "");
+        assert_eq!(gdb.request("\u{e9}"), "", "multi-byte command");
+        assert_eq!(gdb.request(""), "");
+        assert_eq!(gdb.request(b"M20,1:\xff"), "");
+        assert_eq!(gdb.request("X20,0:"), "OK", "GDB's probe for X");
+        // 7d 23 24, escaped as `}` followed by the byte xor 0x20
+        assert_eq!(gdb.request(b"X20,3:}]}\x03}\x04"), "OK");
+        assert_eq!(gdb.request("m20,3"), "7d2324");
+        assert_eq!(gdb.request("X20,1:}"), "E00", "escape cut short");
+        assert_eq!(gdb.request("X20,2:a"), "E00");
+    });
+    assert_eq!(dbg.machine().bus().prog.read_byte(0x20), 0x7D);
+}
+
+#[test]
+fn breakpoints_watchpoints_and_interrupts() {
+    let mut dbg = debugger();
+    session(&mut dbg, |gdb| {
+        assert_eq!(gdb.request("Z0,10,1"), "OK");
+        assert_eq!(gdb.request("c"), "S05");
+        assert_eq!(gdb.request("p12"), "1000");
+        assert_eq!(gdb.request("p13"), "0800", "s0 holds the return address");
+        assert_eq!(gdb.request("s"), "S05");
+        assert_eq!(gdb.request("p12"), "1100");
+        assert_eq!(gdb.request("z0,10,1"), "OK");
+
+        assert_eq!(gdb.request("Z0,1000,1"), "E14", "past program memory");
+        assert_eq!(gdb.request("Z0,11,1"), "OK");
+        assert_eq!(gdb.request("Z1,11,1"), "OK");
+        assert_eq!(gdb.request("z0,11,1"), "OK");
+        assert_eq!(gdb.request("c"), "S05", "the Z1 breakpoint stays");
+        assert_eq!(gdb.request("p12"), "1100");
+        assert_eq!(gdb.request("z1,11,1"), "OK");
+
+        assert_eq!(gdb.request("Z2,10053,1"), "OK");
+        assert_eq!(gdb.request("P12=0000"), "OK");
+        assert_eq!(gdb.request("c"), "T05watch:10053;");
+        assert_eq!(gdb.request("p12"), "0500");
+        assert_eq!(gdb.request("z2,10053,1"), "OK");
+        assert_eq!(gdb.request("Z2,30000,1"), "E14");
+
+        gdb.send("c");
+        thread::sleep(Duration::from_millis(50));
+        gdb.writer.write_all(&[0x03]).unwrap();
+        assert_eq!(gdb.reply(), "S02");
+    });
+    assert!(dbg.breakpoints().is_empty());
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use intel_4004::chips::ShiftRegister4003;
+use intel_4004::dev::keyboard::{Columns, KeyMatrix, UnknownKey};
+use intel_4004::dev::shared;
+
+use common::machine;
+
+// LDM 4; WRR (drive column 2) | RDR; KBP | JUN 002H
+const SCAN_COLUMN_2: &[u8] = &[0xD4, 0xE2, 0xEA, 0xFC, 0x40, 0x02];
+
+fn layout(columns: Columns) -> KeyMatrix {
+    KeyMatrix::new(columns)
+        .with_key("1", 0, 0)
+        .with_key("5", 2, 1)
+        .with_key("9", 2, 3)
+        .with_key("+", 7, 2)
+}
+
+#[test]
+fn kbp_decodes_pressed_row() {
+    let keys = shared(layout(Columns::Port));
+    keys.borrow_mut().press("5").unwrap();
+    let mut m = machine(&keys, SCAN_COLUMN_2);
+    m.run_steps(4);
+    assert_eq!(m.cpu().acc(), 2);
+}
+
+#[test]
+fn keys_in_other_columns_are_not_seen() {
+    let keys = shared(layout(Columns::Port));
+    keys.borrow_mut().press("1").unwrap();
+    let mut m = machine(&keys, SCAN_COLUMN_2);
+    m.run_steps(4);
+    assert_eq!(m.cpu().acc(), 0);
+}
+
+#[test]
+fn two_keys_in_one_column_are_rejected_by_kbp() {
+    let keys = shared(layout(Columns::Port));
+    keys.borrow_mut().press("5").unwrap();
+    keys.borrow_mut().press("9").unwrap();
+    let mut m = machine(&keys, SCAN_COLUMN_2);
+    m.run_steps(4);
+    assert_eq!(m.cpu().acc(), 0xF);
+}
+
+#[test]
+fn tapped_keys_follow_hold_and_debounce_timing() {
+    let keys = shared(layout(Columns::Port).with_timing(100, 100));
+    keys.borrow_mut().type_keys(&["5", "9"]).unwrap();
+    let mut m = machine(&keys, SCAN_COLUMN_2);
+
+    m.run_until(|cpu| cpu.cycles() >= 50);
+    assert!(keys.borrow().is_pressed("5"));
+    m.run_until(|cpu| cpu.cycles() >= 150);
+    assert!(!keys.borrow().is_pressed("5"));
+    assert!(!keys.borrow().is_pressed("9"));
+    m.run_until(|cpu| cpu.cycles() >= 250);
+    assert!(keys.borrow().is_pressed("9"));
+    m.run_until(|cpu| cpu.cycles() >= 450);
+    assert!(!keys.borrow().is_busy());
+}
+
+#[test]
+fn columns_from_shift_register() {
+    let scan = shared(ShiftRegister4003::new(1));
+    let keys = shared(layout(Columns::ShiftRegister(scan.clone())));
+    keys.borrow_mut().press("+").unwrap();
+
+    scan.borrow_mut().shift_in(true);
+    for _ in 0..7 {
+        scan.borrow_mut().shift_in(false);
+    }
+    let mut m = machine(&keys, &[0xEA, 0xFC]); // RDR; KBP
+    m.run_steps(2);
+    assert_eq!(m.cpu().acc(), 3);
+}
+
+#[test]
+fn unknown_keys_are_reported_not_fatal() {
+    let mut keys = layout(Columns::Port);
+    let unknown = Err(UnknownKey("7".to_string()));
+    assert_eq!(keys.press("7"), unknown);
+    assert_eq!(keys.release("7"), unknown);
+    assert_eq!(keys.tap("7"), unknown);
+    assert!(!keys.is_pressed("7"));
+
+    assert_eq!(keys.type_keys(&["1", "7"]), unknown);
+    assert!(!keys.is_busy(), "nothing queued");
+}
@@ -0,0 +1,63 @@
+use intel_4004::bus::standard::StandardBus;
+use intel_4004::chips::{DataRam4002, Mem4289};
+use intel_4004::machine::Machine;
+
+fn machine(mem: Mem4289) -> Machine<StandardBus> {
+    Machine::new(StandardBus::new(mem, DataRam4002::default()))
+}
+
+// FIM P0,E0H; SRC P0; LDM 1; WRR    → page register = 1
+// FIM P0,00H; SRC P0; LDM D; WPM; LDM 9; WPM  → [100H] = D9 (LDM 9)
+const WRITE_100H: &[u8] = &[
+    0x20, 0xE0, 0x21, 0xD1, 0xE2, 0x20, 0x00, 0x21, 0xDD, 0xE3, 0xD9, 0xE3,
+];
+
+#[test]
+fn wpm_writes_ram_page_and_runs_it() {
+    let mut rom = WRITE_100H.to_vec();
+    rom.extend([0xD0, 0x41, 0x00]); // LDM 0; JUN 100H
+    let mut m = machine(Mem4289::from_bytes(&rom).with_ram(1..2));
+    m.run_steps(12);
+    assert_eq!(m.bus().prog.read_byte(0x100), 0xD9);
+    assert_eq!(m.cpu().pc(), 0x100);
+    m.step();
+    assert_eq!(m.cpu().acc(), 9);
+}
+
+#[test]
+fn wpm_ignores_eprom_pages() {
+    let mut m = machine(Mem4289::from_bytes(WRITE_100H));
+    m.run_steps(10);
+    assert_eq!(m.bus().prog.read_byte(0x100), 0x00);
+}
+
+#[test]
+fn rdr_on_port_15_reads_program_memory() {
+    let mut mem = Mem4289::from_bytes(&[
+        0x20, 0xE0, 0x21, 0xD2, 0xE2, // FIM P0,E0H; SRC P0; LDM 2; WRR → page 2
+        0x20, 0xF5, 0x21, // FIM P0,F5H; SRC P0 → [2F5H], port 15
+        0xEA, 0xB2, 0xEA, // RDR; XCH R2; RDR
+    ]);
+    let mut eprom = [0u8; 256];
+    eprom[0xF5] = 0x4C;
+    mem.load_eprom(2, &eprom);
+    let mut m = machine(mem);
+    m.run_steps(10);
+    assert_eq!(m.cpu().reg(2), 0x4);
+    assert_eq!(m.cpu().acc(), 0xC);
+}
+
+#[test]
+fn src_restarts_at_the_high_half() {
+    let mut mem = Mem4289::from_bytes(&[
+        0x20, 0xE0, 0x21, 0xD2, 0xE2, // FIM P0,E0H; SRC P0; LDM 2; WRR → page 2
+        0x20, 0xF5, 0x21, 0xEA, // FIM P0,F5H; SRC P0; RDR → high half
+        0x21, 0xEA, // SRC P0; RDR → high half again
+    ]);
+    let mut eprom = [0u8; 256];
+    eprom[0xF5] = 0x4C;
+    mem.load_eprom(2, &eprom);
+    let mut m = machine(mem);
+    m.run_steps(9);
+    assert_eq!(m.cpu().acc(), 0x4);
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use intel_4004::dev::mock::{Access, MockDevice, Record};
+use intel_4004::dev::shared;
+
+use common::machine;
+
+// Terminal-style echo: RDR; JCN 4H,00H | RDR; XCH R0 | RDR; XCH R1 | LD R0; WRR | LD R1; WRR | JUN 000H
+#[rustfmt::skip]
+const ECHO: &[u8] = &[
+    0xEA, 0x14, 0x00,
+    0xEA, 0xB0, 0xEA, 0xB1,
+    0xA0, 0xE2, 0xA1, 0xE2,
+    0x40, 0x00,
+];
+
+#[test]
+fn plays_a_script_of_reads_and_writes() {
+    // No byte, then 'A' (status, hi, lo), echoed back as two nibbles.
+    let mock = shared(
+        MockDevice::new()
+            .expect_reads(&[0, 1, 4, 1])
+            .expect_writes(&[4, 1])
+            .expect_read(0),
+    );
+    let mut m = machine(&mock, ECHO);
+    m.run_steps(2 + 10 + 2);
+    let mock = mock.borrow();
+    mock.assert_done();
+    assert_eq!(mock.writes(), [4, 1]);
+    let records = mock.records();
+    assert_eq!(records.len(), 7);
+    assert_eq!(
+        records[..2],
+        [
+            Record {
+                access: Access::Read(0),
+                pc: 0x000,
+                cycle: 0
+            },
+            Record {
+                access: Access::Read(1),
+                pc: 0x000,
+                cycle: 24
+            },
+        ]
+    );
+    assert_eq!(
+        records[5],
+        Record {
+            access: Access::Write(1),
+            pc: 0x00A,
+            cycle: 24 + 80
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "expected write 2, got write 1 at PC 00A (cycle 80)")]
+fn mismatch_names_the_pc() {
+    let mock = shared(
+        MockDevice::new()
+            .expect_reads(&[1, 4, 1])
+            .expect_writes(&[4, 2]),
+    );
+    let mut m = machine(&mock, ECHO);
+    m.run_steps(10);
+}
+
+#[test]
+fn default_read_answers_after_the_script() {
+    let mock = shared(MockDevice::new().with_default_read(0));
+    let mut m = machine(&mock, ECHO);
+    m.run_steps(6);
+    let records = mock.borrow().records().to_vec();
+    assert_eq!(records.len(), 3);
+    assert!(
+        records
+            .iter()
+            .all(|r| r.access == Access::Read(0) && r.pc == 0)
+    );
+}
+
+#[test]
+#[should_panic(expected = "1 step(s) left, next is any write")]
+fn assert_done_reports_leftover_steps() {
+    let mock = shared(MockDevice::new().expect_read(1).expect_any_write());
+    let mut m = machine(&mock, ECHO);
+    m.run_steps(2);
+    assert_eq!(mock.borrow().remaining(), 1);
+    mock.borrow().assert_done();
+}
@@ -0,0 +1,73 @@
+use intel_4004::bus::simple::SimpleBus;
+use intel_4004::bus::standard::StandardBus;
+use intel_4004::chips::{DataRam4002, Mem4289, Rom4001};
+use intel_4004::dev::panel::{self, LedBank, SwitchPanel};
+use intel_4004::dev::{Shared, shared};
+use intel_4004::machine::Machine;
+
+// RDR; WMP; JUN 000H
+const COPY_SWITCHES: &[u8] = &[0xEA, 0xE1, 0x40, 0x00];
+
+fn machine(switches: &Shared<SwitchPanel>, leds: &Shared<LedBank>) -> Machine<SimpleBus> {
+    let mut rom = Rom4001::from_bytes(COPY_SWITCHES);
+    rom.attach_port(switches.clone());
+    let mut ram = DataRam4002::default();
+    ram.attach_port(leds.clone());
+    Machine::new(SimpleBus::new(rom, ram))
+}
+
+#[test]
+fn leds_follow_the_switches() {
+    let switches = shared(SwitchPanel::new().with_value(0b0011));
+    let leds = shared(LedBank::new());
+    let mut m = machine(&switches, &leds);
+    m.run_steps(3);
+    assert_eq!(leds.borrow().render(), "..**");
+    switches.borrow_mut().flip(3);
+    switches.borrow_mut().set_switch(0, false);
+    assert_eq!(switches.borrow().render(), "^v^v");
+    m.run_steps(3);
+    assert_eq!(leds.borrow().value(), 0b1010);
+    assert!(leds.borrow().is_lit(3) && !leds.borrow().is_lit(0));
+    let history: Vec<_> = leds.borrow().history().collect();
+    assert_eq!(history, [(8, 0b0011), (40, 0b1010)]);
+}
+
+#[test]
+fn history_keeps_the_latest_writes() {
+    let switches = shared(SwitchPanel::new());
+    let leds = shared(LedBank::new().with_history(2));
+    let mut m = machine(&switches, &leds);
+    for v in 1..=3 {
+        switches.borrow_mut().set(v);
+        m.run_steps(3);
+    }
+    let values: Vec<u8> = leds.borrow().history().map(|(_, v)| v).collect();
+    assert_eq!(values, [2, 3]);
+    leds.borrow_mut().clear_history();
+    assert_eq!(leds.borrow().history().count(), 0);
+}
+
+#[test]
+fn front_panel_shows_cpu_and_ports() {
+    let switches = shared(SwitchPanel::new().with_value(0xA));
+    let leds = shared(LedBank::new());
+    let mut m = machine(&switches, &leds);
+    m.run_steps(2);
+    let text = panel::render(&m);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "PC  002  ACC  *.*. A  CY .");
+    assert_eq!(lines[1], "R0  .... 0  R1  .... 0  R2  .... 0  R3  .... 0");
+    assert_eq!(lines[4], "R12 .... 0  R13 .... 0  R14 .... 0  R15 .... 0");
+    assert_eq!(lines[5], "ROM .... 0  RAM *.*. A");
+
+    // A 4289 system shows all sixteen I/O ports, the page register on IOE.
+    let mut m = Machine::new(StandardBus::new(
+        Mem4289::from_bytes(&[0x20, 0xE0, 0x21, 0xD3, 0xE2]), // FIM P0,E0H; SRC P0; LDM 3; WRR
+        DataRam4002::default(),
+    ));
+    m.run_steps(4);
+    let text = panel::render(&m);
+    assert_eq!(text.lines().count(), 1 + 4 + 5);
+    assert!(text.contains("IOE ..** 3"));
+}
@@ -0,0 +1,68 @@
+use intel_4004::bus::simple::SimpleBus;
+use intel_4004::chips::{DataRam4002, Rom4001, ShiftRegister4003};
+use intel_4004::dev::printer::DrumPrinter;
+use intel_4004::dev::{Shared, shared};
+use intel_4004::machine::Machine;
+
+fn machine(bytes: &[u8], sector_cycles: u64) -> (Machine<SimpleBus>, Shared<DrumPrinter>) {
+    let hammers = shared(ShiftRegister4003::new(2));
+    let mut rom = Rom4001::from_bytes(bytes);
+    rom.attach_port(hammers.clone());
+
+    let mut m = Machine::new(SimpleBus::new(rom, DataRam4002::default()));
+    let printer = shared(
+        DrumPrinter::new(hammers, m.test_line()).with_timing(sector_cycles, sector_cycles / 2),
+    );
+    m.bus_mut().data.attach_port(printer.clone());
+    (m, printer)
+}
+
+#[test]
+fn fires_character_under_the_hammer() {
+    #[rustfmt::skip]
+    let (mut m, printer) = machine(&[
+        0xD2, 0xE2, 0xD3, 0xE2, 0xD0, 0xE2, // shift a 1 into column 0
+        0xD2, 0xE1,                         // cycle 48: LDM 2; WMP → fire during sector 3
+        0xD8, 0xE1,                         // LDM 8; WMP → advance paper
+    ], 16);
+    m.run_steps(10);
+    assert_eq!(printer.borrow().lines(), ["3"]);
+}
+
+#[test]
+fn unselected_columns_stay_blank() {
+    #[rustfmt::skip]
+    let (mut m, printer) = machine(&[
+        0xD2, 0xE2, 0xD3, 0xE2, 0xD0, 0xE2, // shift a 1 into column 0
+        0xD0, 0xE2, 0xD1, 0xE2,             // shift a 0 in → the 1 moves to column 1
+        0xD0, 0xE1, 0xD2, 0xE1,             // fire during sector 0
+        0xD8, 0xE1,
+    ], 1000);
+    m.run_steps(16);
+    assert_eq!(printer.borrow().text(), " 0", "only column 1 prints");
+}
+
+#[test]
+fn test_pin_pulses_every_sector() {
+    let (mut m, printer) = machine(&[0x40, 0x00], 100); // JUN 000H
+    let test = m.test_line();
+    let mut pulses = 0;
+    let mut last = test.get();
+    m.run_until(|cpu| {
+        if test.get() && !last {
+            pulses += 1;
+        }
+        last = test.get();
+        cpu.cycles() >= 1000
+    });
+    assert_eq!(pulses, 10); // rising edges at 100, 200, … 1000
+    assert_eq!(printer.borrow().sector(), 10);
+}
+
+#[test]
+fn jcn_waits_on_test_pin() {
+    // 000: JCN 9H,00H → loop while TEST high | 002: LDM 7
+    let (mut m, _printer) = machine(&[0x19, 0x00, 0xD7], 160);
+    m.run_until(|cpu| cpu.acc() == 7);
+    assert_eq!(m.cycles(), 104); // pulse ends at 80, the JCN after it falls through
+}
@@ -0,0 +1,171 @@
+#![cfg(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "arm",
+        target_arch = "aarch64",
+        target_arch = "riscv32",
+        target_arch = "riscv64"
+    )
+))]
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use intel_4004::bus::simple::SimpleBus;
+use intel_4004::chips::{DataRam4002, Rom4001};
+use intel_4004::dev::pty::{AVAILABLE, ERROR, PtyDevice};
+use intel_4004::dev::{IoDevice, shared};
+use intel_4004::machine::Machine;
+
+const O_NONBLOCK: i32 = 0o4000;
+
+fn machine(pty: PtyDevice, bytes: &[u8]) -> Machine<SimpleBus> {
+    let mut rom = Rom4001::from_bytes(bytes);
+    rom.attach_port(pty);
+    Machine::new(SimpleBus::new(rom, DataRam4002::default()))
+}
+
+fn client(pty: &PtyDevice) -> File {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(O_NONBLOCK)
+        .open(pty.path())
+        .unwrap()
+}
+
+/// Runs until the ROM reaches `pc`, giving the host side a second to answer.
+fn run_to(m: &mut Machine<SimpleBus>, pc: u16) {
+    let deadline = Instant::now() + Duration::from_secs(1);
+    while m.cpu().pc() != pc {
+        assert!(
+            Instant::now() < deadline,
+            "ROM stuck at {:03X}",
+            m.cpu().pc()
+        );
+        m.step();
+    }
+}
+
+fn read_exact(file: &mut File, n: usize) -> Vec<u8> {
+    let deadline = Instant::now() + Duration::from_secs(1);
+    let mut out = Vec::new();
+    let mut buf = [0u8; 16];
+    while out.len() < n {
+        assert!(Instant::now() < deadline, "got {out:?}");
+        if let Ok(k) = file.read(&mut buf) {
+            out.extend(&buf[..k]);
+        }
+    }
+    out
+}
+
+#[test]
+fn nibble_mode_echoes_through_the_slave() {
+    let pty = PtyDevice::open().unwrap();
+    let mut host = client(&pty);
+    host.write_all(b"ok").unwrap();
+    // RDR; JCN AZ,000H | RDR; XCH R0 | RDR; XCH R1 | LD R0; WRR | LD R1; WRR | JUN 000H
+    #[rustfmt::skip]
+    let mut m = machine(pty, &[
+        0xEA, 0x14, 0x00,
+        0xEA, 0xB0, 0xEA, 0xB1,
+        0xA0, 0xE2, 0xA1, 0xE2,
+        0x40, 0x00,
+    ]);
+    for _ in 0..2 {
+        run_to(&mut m, 0x00B);
+        m.step();
+    }
+    assert_eq!(read_exact(&mut host, 2), b"ok");
+}
+
+#[test]
+fn uart_mode_decodes_bit_banged_bytes() {
+    let pty = PtyDevice::open().unwrap().with_uart(16, 0, 0);
+    let mut host = client(&pty);
+    // One LDM; WRR pair per bit: 16 cycles. 'A' = 0x41, LSB first.
+    let mut rom = Vec::new();
+    for bit in [1, 0, 1, 0, 0, 0, 0, 0, 1, 0, 1] {
+        rom.extend([0xD0 | bit, 0xE2]);
+    }
+    rom.extend([0x40, rom.len() as u8]);
+    let end = rom.len() as u16 - 2;
+    let mut m = machine(pty, &rom);
+    run_to(&mut m, end);
+    m.run_steps(4);
+    assert_eq!(read_exact(&mut host, 1), b"A");
+}
+
+#[test]
+fn uart_mode_drives_the_receive_line() {
+    let pty = PtyDevice::open().unwrap().with_uart(16, 0, 0);
+    let mut host = client(&pty);
+    host.write_all(b"Z").unwrap();
+    // Wait for the start bit: RDR; JCN AN,000H. Then sample every 16 cycles:
+    // RDR; XCH Rn for the eight data bits and the stop bit.
+    let mut rom = vec![0xEA, 0x1C, 0x00];
+    for r in 0..9 {
+        rom.extend([0xEA, 0xB0 | r]);
+    }
+    let end = rom.len() as u16;
+    rom.extend([0x40, end as u8]);
+    let mut m = machine(pty, &rom);
+    run_to(&mut m, end);
+    let byte = (0..8).fold(0, |b, r| b | m.cpu().reg(r) << r);
+    assert_eq!(byte, b'Z');
+    assert_eq!(m.cpu().reg(8), 1);
+}
+
+#[test]
+fn writes_the_pty_cannot_take_are_counted() {
+    let pty = shared(PtyDevice::open().unwrap());
+    // Nobody reads the slave. LDM 5; WRR; LDM A; WRR | JUN 000H
+    let mut rom = Rom4001::from_bytes(&[0xD5, 0xE2, 0xDA, 0xE2, 0x40, 0x00]);
+    rom.attach_port(pty.clone());
+    let mut m = Machine::new(SimpleBus::new(rom, DataRam4002::default()));
+    m.run_steps(1_000_000);
+    let mut pty = pty.borrow_mut();
+    let stats = pty.stats();
+    assert!(stats.sent > 0 && stats.failed > 0, "{stats:?}");
+    assert_eq!(pty.read4() & ERROR, ERROR);
+    assert!(pty.take_error().is_some());
+    assert_eq!(pty.read4() & ERROR, 0);
+}
+
+#[test]
+fn reads_without_a_client_do_not_block() {
+    // Pins O_NONBLOCK: with the wrong flag value the read would hang.
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut pty = PtyDevice::open().unwrap();
+        tx.send(pty.read4()).unwrap();
+    });
+    assert_eq!(rx.recv_timeout(Duration::from_secs(2)), Ok(0));
+}
+
+#[test]
+fn the_slave_is_raw() {
+    // Pins the termios layout: cfmakeraw took effect, so output is not
+    // translated ("\n" stays "\n") and input is not echoed back.
+    let mut pty = PtyDevice::open().unwrap();
+    let mut host = client(&pty);
+    host.write_all(b"\n").unwrap();
+    pty.write4(0x4);
+    pty.write4(0x1);
+    assert_eq!(read_exact(&mut host, 1), b"A");
+
+    let deadline = Instant::now() + Duration::from_secs(1);
+    while pty.read4() != AVAILABLE {
+        assert!(Instant::now() < deadline, "nothing from the host");
+    }
+    assert_eq!([pty.read4(), pty.read4()], [0x0, 0xA]);
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(pty.read4(), 0, "no CR added, no echo");
+}
@@ -0,0 +1,68 @@
+use intel_4004::bus::simple::SimpleBus;
+use intel_4004::chips::{DataRam4002, Rom4001};
+use intel_4004::dev::IoDevice;
+use intel_4004::dev::random::{Distribution, RandomDevice};
+use intel_4004::machine::Machine;
+
+fn nibbles(dev: &mut RandomDevice, n: usize) -> Vec<u8> {
+    (0..n).map(|_| dev.read4()).collect()
+}
+
+#[test]
+fn same_seed_gives_same_nibbles() {
+    let a = nibbles(&mut RandomDevice::seeded(42), 64);
+    assert_eq!(a, nibbles(&mut RandomDevice::seeded(42), 64));
+    assert_ne!(a, nibbles(&mut RandomDevice::seeded(43), 64));
+    assert!(a.iter().all(|&n| n < 16));
+    let mut seen = [false; 16];
+    a.iter().for_each(|&n| seen[n as usize] = true);
+    assert!(seen.iter().filter(|&&s| s).count() > 10);
+}
+
+#[test]
+fn rom_reseeds_and_selects_a_distribution() {
+    // RESEED 1234; RANGE 5; then RDR; XCH Rn x8
+    let mut rom: Vec<u8> = [1, 1, 2, 3, 4, 3, 5]
+        .iter()
+        .flat_map(|&n| [0xD0 | n, 0xE2])
+        .collect();
+    for r in 0..8 {
+        rom.extend([0xEA, 0xB0 | r]);
+    }
+    let mut rom = Rom4001::from_bytes(&rom);
+    rom.attach_port(RandomDevice::from_entropy());
+    let mut m = Machine::new(SimpleBus::new(rom, DataRam4002::default()));
+    m.run_steps(14 + 16);
+    let regs: Vec<u8> = (0..8).map(|r| m.cpu().reg(r)).collect();
+    let mut expected = RandomDevice::seeded(0x1234).with_distribution(Distribution::Range(5));
+    assert_eq!(regs, nibbles(&mut expected, 8));
+    assert!(regs.iter().all(|&n| n <= 5));
+}
+
+#[test]
+fn distributions_stay_in_range() {
+    let mut coin = RandomDevice::seeded(7).with_distribution(Distribution::Bernoulli(0));
+    assert!(nibbles(&mut coin, 32).iter().all(|&n| n == 0));
+    coin.write4(4);
+    coin.write4(8);
+    let heads = nibbles(&mut coin, 256).iter().filter(|&&n| n == 1).count();
+    assert!((64..192).contains(&heads), "{heads}");
+    let mut bell = RandomDevice::seeded(7).with_distribution(Distribution::Bell);
+    let bell = nibbles(&mut bell, 256);
+    let middle = bell.iter().filter(|&&n| (4..=11).contains(&n)).count();
+    assert!(middle > 200, "{middle}");
+}
+
+#[test]
+fn restore_replays_the_sequence() {
+    let mut dev = RandomDevice::from_entropy();
+    nibbles(&mut dev, 5);
+    let saved = dev.state();
+    let first = nibbles(&mut dev, 16);
+    dev.write4(2);
+    dev.restore(&saved);
+    assert_eq!(nibbles(&mut dev, 16), first);
+    let mut replay = RandomDevice::seeded(dev.seed());
+    nibbles(&mut replay, 5);
+    assert_eq!(nibbles(&mut replay, 16), first);
+}
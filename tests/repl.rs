@@ -0,0 +1,103 @@
+mod common;
+
+use intel_4004::bus::simple::SimpleBus;
+use intel_4004::debugger::{Debugger, repl};
+
+use common::debugger;
+
+fn session(dbg: &mut Debugger<SimpleBus>, input: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    repl::run(dbg, input.as_bytes(), &mut out).unwrap();
+    String::from_utf8(out)
+        .unwrap()
+        .split("(4004) ")
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn steps_and_stops_on_breakpoints() {
+    let mut dbg = debugger();
+    let out = session(
+        &mut dbg,
+        "step\n\nb 10H if acc==0\nwatch write 0:1:1:3\nc\nc\nn\nfinish\nfinish\ninfo\nbogus\n",
+    );
+    assert_eq!(out[0], "=> 000H  20 53    FIM P0,53H\n");
+    assert_eq!(out[1], "=> 002H  21       SRC P0\n");
+    assert_eq!(out[2], "=> 003H  D7       LDM 7\n", "empty line repeats");
+    assert_eq!(out[3], "Breakpoint 1 at 010H\n");
+    assert_eq!(out[4], "Watchpoint 2: write bank 0 chip 1 reg 1 char 3\n");
+    assert_eq!(
+        out[5],
+        "Stopped at watchpoint 2: write bank 0 chip 1 reg 1 char 3 = 7 at 004H\n\
+         => 005H  E9       RDM\n"
+    );
+    // ACC is 7 on the first call, 0 on the second.
+    assert_eq!(
+        out[6],
+        "Stopped at breakpoint 1, 010H\n=> 010H  F2       IAC\n"
+    );
+    assert_eq!(out[7], "=> 011H  C0       BBL 0\n");
+    assert_eq!(out[8], "=> 008H  40 06    JUN 006H\n");
+    assert_eq!(out[9], "No subroutine to finish\n");
+    assert_eq!(
+        out[10],
+        "  1  PC 010H if condition, hit 1 time(s)\n  \
+         2  watch write bank 0 chip 1 reg 1 char 3, hit 1 time(s)\n"
+    );
+    assert_eq!(out[11], "unknown command `bogus`, try `help`\n");
+    assert_eq!(out[12], "\n");
+}
+
+#[test]
+fn edits_and_shows_registers_and_ram() {
+    let mut dbg = debugger();
+    let out = session(
+        &mut dbg,
+        "set acc c\nset r13 5\nset pc 10\nset 2:3:0:f 9\nset 2:3:0:s1 4\nregs\nram 2\ndisas 0 4\nq\nregs\n",
+    );
+    assert_eq!(out.len(), 10, "nothing after quit");
+    assert_eq!(
+        out[6],
+        "PC 010H  ACC C  CY 0  cycles 0\n\
+         R0-R7   0 0 0 0 0 0 0 0\n\
+         R8-R15  0 0 0 0 0 5 0 0\n\
+         stack   000H 000H 000H  SP 0\n"
+    );
+    let ram: Vec<&str> = out[7].lines().collect();
+    assert_eq!(ram.len(), 17);
+    assert_eq!(ram[13], "chip 3 reg 0  0000000000000009  0400");
+    assert_eq!(
+        out[8],
+        "   000H  20 53    FIM P0,53H\n   002H  21       SRC P0\n   \
+         003H  D7       LDM 7\n   004H  E0       WRM\n"
+    );
+    let cpu = dbg.machine().cpu();
+    assert_eq!((cpu.pc(), cpu.acc(), cpu.reg(13)), (0x010, 0xC, 5));
+}
+
+#[test]
+fn watches_4289_ports() {
+    let mut dbg = debugger();
+    let out = session(&mut dbg, "watch write io3\nwatch ioG\n");
+    assert_eq!(out[1], "Watchpoint 1: write IO3 port\n");
+    assert_eq!(out[2], "bad port `ioG`, e.g. rom, ram or io3\n");
+}
+
+#[test]
+fn rejects_values_out_of_range() {
+    let mut dbg = debugger();
+    let out = session(
+        &mut dbg,
+        "break 10000\nb 1000H\nset acc 10\nset cy 2\nset 0:0:0:0 1F\nb 10 if r1==10\nram 8\nset pc FFF\n",
+    );
+    assert_eq!(out[1], "`10000` is out of range, at most FFFH\n");
+    assert_eq!(out[2], "`1000H` is out of range, at most FFFH\n");
+    assert_eq!(out[3], "`10` is out of range, at most FH\n");
+    assert_eq!(out[4], "`2` is out of range, at most 1H\n");
+    assert_eq!(out[5], "`1F` is out of range, at most FH\n");
+    assert_eq!(out[6], "`10` is out of range, at most FH\n");
+    assert_eq!(out[7], "`8` is out of range, at most 7H\n");
+    assert!(dbg.breakpoints().is_empty());
+    assert_eq!(dbg.machine().cpu().pc(), 0xFFF);
+}
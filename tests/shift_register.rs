@@ -0,0 +1,53 @@
+use intel_4004::bus::simple::SimpleBus;
+use intel_4004::chips::{DataRam4002, Rom4001, ShiftRegister4003};
+use intel_4004::dev::{Shared, shared};
+use intel_4004::machine::Machine;
+
+fn run(sr: &Shared<ShiftRegister4003>, bytes: &[u8], steps: usize) {
+    let mut rom = Rom4001::from_bytes(bytes);
+    rom.attach_port(sr.clone());
+    let mut m = Machine::new(SimpleBus::new(rom, DataRam4002::default()));
+    m.run_steps(steps);
+}
+
+#[test]
+fn shifts_on_rising_clock_edge() {
+    let sr = shared(ShiftRegister4003::new(1));
+    // data=1: LDM 2; WRR; LDM 3; WRR | data=0: LDM 0; WRR; LDM 1; WRR | data=1 again
+    #[rustfmt::skip]
+    run(&sr, &[
+        0xD2, 0xE2, 0xD3, 0xE2,
+        0xD0, 0xE2, 0xD1, 0xE2,
+        0xD2, 0xE2, 0xD3, 0xE2,
+    ], 12);
+    let sr = sr.borrow();
+    assert_eq!(&sr.outputs()[..4], &[true, false, true, false]);
+}
+
+#[test]
+fn holding_clock_high_shifts_once() {
+    let sr = shared(ShiftRegister4003::new(1));
+    run(&sr, &[0xD3, 0xE2, 0xD3, 0xE2, 0xD3, 0xE2], 6); // LDM 3; WRR ×3
+    assert_eq!(sr.borrow().outputs().iter().filter(|&&b| b).count(), 1);
+}
+
+#[test]
+fn chained_stages_carry_serial_out() {
+    let mut sr = ShiftRegister4003::new(2);
+    sr.shift_in(true);
+    for _ in 0..10 {
+        sr.shift_in(false);
+    }
+    assert!(!sr.output(0));
+    assert!(sr.output(10));
+    assert!(!sr.serial_out());
+}
+
+#[test]
+fn outputs_follow_enable_bit() {
+    let sr = shared(ShiftRegister4003::new(1).with_enable_bit(2));
+    run(&sr, &[0xD2, 0xE2, 0xD3, 0xE2], 4); // shift in 1, enable low
+    assert!(!sr.borrow().output(0));
+    run(&sr, &[0xD4, 0xE2], 2); // enable high, clock low
+    assert!(sr.borrow().output(0));
+}
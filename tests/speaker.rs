@@ -0,0 +1,82 @@
+use intel_4004::bus::simple::SimpleBus;
+use intel_4004::chips::{DataRam4002, Rom4001};
+use intel_4004::dev::speaker::{AMPLITUDE, Speaker};
+use intel_4004::dev::terminal::Capture;
+use intel_4004::dev::{Shared, shared};
+use intel_4004::machine::{CLOCK_HZ, Machine};
+
+// LDM 1; WRR; LDM 0; WRR; JUN 000H: 16 cycles high, 32 low, 15.4 kHz.
+const SQUARE: &[u8] = &[0xD1, 0xE2, 0xD0, 0xE2, 0x40, 0x00];
+
+// One sample every 8 cycles, so every instruction is one sample.
+const RATE: u32 = (CLOCK_HZ / 8) as u32;
+
+fn machine(speaker: &Shared<Speaker>) -> Machine<SimpleBus> {
+    let mut rom = Rom4001::from_bytes(SQUARE);
+    rom.attach_port(speaker.clone());
+    Machine::new(SimpleBus::new(rom, DataRam4002::default()))
+}
+
+#[test]
+fn samples_the_port_bit_against_cycles() {
+    let speaker = shared(Speaker::new(RATE));
+    let mut m = machine(&speaker);
+    m.run_steps(5 * 10);
+    let samples = speaker.borrow().samples().to_vec();
+    assert_eq!(samples.len(), 60);
+    let (hi, lo) = (AMPLITUDE, -AMPLITUDE);
+    // High from the first WRR (cycle 8) to the second (cycle 24).
+    assert_eq!(samples[..7], [lo, hi, hi, lo, lo, lo, lo]);
+    assert!(
+        samples[1..55]
+            .chunks(6)
+            .all(|c| c == [hi, hi, lo, lo, lo, lo])
+    );
+}
+
+#[test]
+fn averages_levels_within_a_sample() {
+    // One sample per loop iteration: high a third of the time.
+    let speaker = shared(Speaker::new(RATE / 6));
+    let mut m = machine(&speaker);
+    m.run_steps(5 * 10);
+    let samples = speaker.borrow().samples().to_vec();
+    assert_eq!(samples.len(), 10);
+    assert!(samples[1..].iter().all(|&s| s == -AMPLITUDE / 3));
+}
+
+#[test]
+fn streams_pcm_without_keeping_it() {
+    let pcm = Capture::default();
+    let speaker = shared(Speaker::new(8_000).with_sink(pcm.clone()));
+    let mut m = machine(&speaker);
+    m.run_until(|cpu| cpu.cycles() >= CLOCK_HZ / 10);
+    assert!(speaker.borrow().samples().is_empty());
+    assert_eq!(pcm.bytes().len(), 1600);
+}
+
+#[test]
+fn writes_wav() {
+    let speaker = shared(Speaker::new(8_000));
+    let mut m = machine(&speaker);
+    m.run_until(|cpu| cpu.cycles() >= CLOCK_HZ / 10);
+    let speaker = speaker.borrow();
+    assert_eq!(speaker.samples().len(), 800);
+    let wav = speaker.wav_bytes();
+    assert_eq!(&wav[..4], b"RIFF");
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(u32::from_le_bytes(wav[24..28].try_into().unwrap()), 8_000);
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(u32::from_le_bytes(wav[40..44].try_into().unwrap()), 1600);
+    let pcm: Vec<u8> = speaker
+        .samples()
+        .iter()
+        .flat_map(|s| s.to_le_bytes())
+        .collect();
+    assert_eq!(wav[44..], pcm);
+
+    let path = std::env::temp_dir().join(format!("intel-4004-speaker-{}.wav", std::process::id()));
+    speaker.write_wav(&path).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), wav);
+    std::fs::remove_file(&path).unwrap();
+}
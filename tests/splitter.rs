@@ -0,0 +1,79 @@
+use intel_4004::bus::simple::SimpleBus;
+use intel_4004::chips::{DataRam4002, Rom4001, ShiftRegister4003};
+use intel_4004::dev::mock::MockDevice;
+use intel_4004::dev::panel::{LedBank, SwitchPanel};
+use intel_4004::dev::shared;
+use intel_4004::dev::splitter::PortSplitter;
+use intel_4004::dev::uart::{SoftUart, UartConfig};
+use intel_4004::machine::Machine;
+
+fn machine(bytes: &[u8], splitter: PortSplitter) -> Machine<SimpleBus> {
+    let mut rom = Rom4001::from_bytes(bytes);
+    rom.attach_port(splitter);
+    Machine::new(SimpleBus::new(rom, DataRam4002::default()))
+}
+
+#[test]
+fn each_device_sees_its_bits_in_place() {
+    let low = shared(LedBank::new());
+    let high = shared(LedBank::new());
+    let log = shared(MockDevice::new().expect_writes(&[0xF, 0x6]));
+    let splitter = PortSplitter::new()
+        .with_device(0b0001, low.clone())
+        .with_device(0b1100, high.clone())
+        .with_tee(log.clone());
+    assert_eq!(splitter.mask(), 0b1101);
+    // LDM F; WRR; LDM 6; WRR
+    let mut m = machine(&[0xDF, 0xE2, 0xD6, 0xE2], splitter);
+    m.run_steps(2);
+    assert_eq!(
+        (low.borrow().value(), high.borrow().value()),
+        (0b0001, 0b1100)
+    );
+    m.run_steps(2);
+    assert_eq!(
+        (low.borrow().value(), high.borrow().value()),
+        (0b0000, 0b0100)
+    );
+    log.borrow().assert_done();
+    let pcs: Vec<u16> = log.borrow().records().iter().map(|r| r.pc).collect();
+    assert_eq!(pcs, [1, 3]);
+}
+
+#[test]
+fn reads_combine_the_masked_bits() {
+    let tee = shared(MockDevice::new().expect_any_write());
+    let splitter = PortSplitter::new()
+        .with_device(0b1000, SwitchPanel::new().with_value(0xF))
+        .with_device(0b0011, SwitchPanel::new().with_value(0x6))
+        .with_tee(tee.clone());
+    // RDR; XCH R0
+    let mut m = machine(&[0xEA, 0xB0], splitter);
+    m.run_steps(2);
+    assert_eq!(m.cpu().reg(0), 0b1010);
+    assert!(tee.borrow().records().is_empty());
+}
+
+#[test]
+fn uart_and_shift_register_share_a_port() {
+    let uart = shared(SoftUart::new(UartConfig::with_cycles_per_bit(32)));
+    let sr = shared(ShiftRegister4003::new(1).with_clock_bit(1).with_data_bit(2));
+    let splitter = PortSplitter::new()
+        .with_device(0b0001, uart.clone())
+        .with_device(0b0110, sr.clone());
+    // Each bit time: LDM line|data; WRR; LDM line|data|clock; WRR (32 cycles).
+    // The UART sends 0xFF (start bit, then ones) while the register is fed.
+    let shifted = [1, 0, 1, 1, 0, 0, 0, 0, 0, 0];
+    let mut rom = Vec::new();
+    for (n, data) in shifted.iter().enumerate() {
+        let bits = (n != 0) as u8 | data << 2;
+        rom.extend([0xD0 | bits, 0xE2, 0xD0 | bits | 0b10, 0xE2]);
+    }
+    rom.extend([0xD1, 0xE2, 0x40, rom.len() as u8 + 2]);
+    let mut m = machine(&rom, splitter);
+    m.run_steps(4 * 10 + 2 + 4);
+    assert_eq!(uart.borrow_mut().recv(), Some(Ok(0xFF)));
+    let outputs: Vec<bool> = (0..10).map(|n| sr.borrow().output(n)).collect();
+    let first_in_last: Vec<bool> = shifted.iter().rev().map(|&b| b == 1).collect();
+    assert_eq!(outputs, first_in_last);
+}
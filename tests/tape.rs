@@ -0,0 +1,91 @@
+mod common;
+
+use std::io::ErrorKind;
+
+use intel_4004::dev::shared;
+use intel_4004::dev::tape::{DATA, EOF, ERROR, READY, TapeDevice};
+
+use common::machine;
+
+#[test]
+fn reads_bytes_until_eof() {
+    let tape = shared(TapeDevice::from_bytes(&[0x12, 0x34]));
+    // (LDM 2; WRR; RDR; XCH Rn; RDR; XCH Rn+1; RDR; XCH Rn+2) twice, then
+    // LDM 2; WRR; RDR; XCH R6: status, hi, lo, status, hi, lo, status
+    #[rustfmt::skip]
+    let mut m = machine(&tape, &[
+        0xD2, 0xE2, 0xEA, 0xB0, 0xEA, 0xB1, 0xEA, 0xB2,
+        0xD2, 0xE2, 0xEA, 0xB3, 0xEA, 0xB4, 0xEA, 0xB5,
+        0xD2, 0xE2, 0xEA, 0xB6,
+    ]);
+    m.run_steps(20);
+    let regs: Vec<u8> = (0..7).map(|r| m.cpu().reg(r)).collect();
+    assert_eq!(
+        regs,
+        [READY | DATA, 1, 2, READY | DATA | EOF, 3, 4, READY | EOF]
+    );
+}
+
+#[test]
+fn writes_through_to_the_file() {
+    let path = std::env::temp_dir().join(format!("intel-4004-tape-{}.bin", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let tape = shared(TapeDevice::open(&path).unwrap());
+    // WRITE_BYTE 'h', WRITE_BYTE 'i', WRITE_NIBBLE A, REWIND, READ_NIBBLE;
+    // RDR (status); RDR; XCH R0
+    #[rustfmt::skip]
+    let mut m = machine(&tape, &[
+        0xD1, 0xE2, 0xD6, 0xE2, 0xD8, 0xE2,
+        0xD1, 0xE2, 0xD6, 0xE2, 0xD9, 0xE2,
+        0xD3, 0xE2, 0xDA, 0xE2,
+        0xD5, 0xE2, 0xD4, 0xE2, 0xEA, 0xEA, 0xB0,
+    ]);
+    m.run_steps(23);
+    assert_eq!(m.cpu().reg(0), 0x6);
+    assert_eq!(tape.borrow().position(), 1);
+    assert_eq!(std::fs::read(&path).unwrap(), [b'h', b'i', 0xA0]);
+    drop(m);
+    drop(tape);
+    let reloaded = TapeDevice::open(&path).unwrap();
+    assert_eq!(reloaded.bytes(), [b'h', b'i', 0xA0]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn busy_tape_drops_commands_and_flags_an_error() {
+    // 7400 characters per second: 100 cycles per operation.
+    let tape = shared(TapeDevice::from_bytes(&[0xAB]).with_chars_per_second(7400));
+    // LDM 2; WRR; RDR; XCH R0..R3 (status, hi, lo, status while busy)
+    // LDM 2; WRR (dropped); RDR; XCH R4; NOP x4; RDR; XCH R5 (ready again)
+    #[rustfmt::skip]
+    let mut m = machine(&tape, &[
+        0xD2, 0xE2, 0xEA, 0xB0, 0xEA, 0xB1, 0xEA, 0xB2, 0xEA, 0xB3,
+        0xD2, 0xE2, 0xEA, 0xB4,
+        0x00, 0x00, 0x00, 0x00, 0xEA, 0xB5,
+    ]);
+    m.run_steps(20);
+    let regs: Vec<u8> = (0..6).map(|r| m.cpu().reg(r)).collect();
+    assert_eq!(
+        regs,
+        [DATA | EOF, 0xA, 0xB, EOF, EOF | ERROR, READY | EOF | ERROR]
+    );
+    let mut tape = tape.borrow_mut();
+    let overrun = tape.take_error().unwrap();
+    assert_eq!(overrun.kind(), ErrorKind::ResourceBusy);
+    assert!(tape.take_error().is_none());
+}
+
+#[test]
+fn short_read_returns_no_data() {
+    let tape = shared(TapeDevice::from_bytes(&[0xAB]));
+    // LDM 4; WRR; RDR; XCH R0; RDR; XCH R1 (nibble A) | LDM 2; WRR; RDR; XCH R2
+    #[rustfmt::skip]
+    let mut m = machine(&tape, &[
+        0xD4, 0xE2, 0xEA, 0xB0, 0xEA, 0xB1,
+        0xD2, 0xE2, 0xEA, 0xB2,
+    ]);
+    m.run_steps(10);
+    let regs: Vec<u8> = (0..3).map(|r| m.cpu().reg(r)).collect();
+    assert_eq!(regs, [READY | DATA, 0xA, READY]);
+    assert_eq!(tape.borrow().position(), 1);
+}
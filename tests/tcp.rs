@@ -0,0 +1,119 @@
+mod common;
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+use intel_4004::dev::tcp::{AVAILABLE, CLOSED, CONNECTED, ERROR, TcpDevice};
+use intel_4004::dev::{IoDevice, shared};
+
+use common::machine;
+
+/// Polls the status until it has all of `bits` set.
+fn wait_for(dev: &mut TcpDevice, bits: u8) {
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while dev.read4() & bits != bits {
+        assert!(Instant::now() < deadline, "status never had {bits:04b}");
+    }
+}
+
+// 000: RDR; RAR; JCN AH,00H → wait for bit 0
+// 004: RDR; XCH R0 | RDR; XCH R1 | LD R0; WRR | LD R1; WRR | JUN 000H
+#[rustfmt::skip]
+const ECHO: &[u8] = &[
+    0xEA, 0xF6, 0x1A, 0x00,
+    0xEA, 0xB0, 0xEA, 0xB1, 0xA0, 0xE2, 0xA1, 0xE2, 0x40, 0x00,
+];
+
+// RDR; XCH R0; JUN 000H
+const STATUS: &[u8] = &[0xEA, 0xB0, 0x40, 0x00];
+
+#[test]
+fn client_echoes_stream() {
+    let server = TcpListener::bind("127.0.0.1:0").unwrap();
+    let dev = shared(TcpDevice::connect(server.local_addr().unwrap()).unwrap());
+    let (mut peer, _) = server.accept().unwrap();
+    peer.set_nonblocking(true).unwrap();
+
+    let mut m = machine(&dev, ECHO);
+    peer.write_all(b"ping").unwrap();
+    let mut buf = [0u8; 4];
+    let mut got = 0;
+    while got < 4 {
+        m.run_steps(100);
+        if let Ok(n) = peer.read(&mut buf[got..]) {
+            got += n;
+        }
+    }
+    assert_eq!(&buf, b"ping");
+}
+
+#[test]
+fn status_without_data_does_not_block() {
+    let server = TcpListener::bind("127.0.0.1:0").unwrap();
+    let dev = shared(TcpDevice::connect(server.local_addr().unwrap()).unwrap());
+    let _peer = server.accept().unwrap();
+
+    let mut m = machine(&dev, STATUS);
+    m.run_steps(100);
+    assert_eq!(m.cpu().reg(0), CONNECTED);
+}
+
+#[test]
+fn server_reports_connection_state() {
+    let dev = shared(TcpDevice::listen("127.0.0.1:0").unwrap());
+    let addr = dev.borrow().local_addr().unwrap();
+    let mut m = machine(&dev, STATUS);
+    m.run_steps(10);
+    assert_eq!(m.cpu().reg(0), 0);
+
+    let mut client = TcpStream::connect(addr).unwrap();
+    m.run_until(|cpu| cpu.reg(0) & CONNECTED != 0);
+    client.write_all(b"!").unwrap();
+    m.run_until(|cpu| cpu.reg(0) & AVAILABLE != 0);
+
+    drop(client);
+    m.run_until(|cpu| cpu.reg(0) & CLOSED != 0);
+    m.run_until(|cpu| cpu.reg(0) == 0);
+    assert!(!dev.borrow().is_connected());
+}
+
+#[test]
+fn next_peer_gets_nothing_meant_for_the_last() {
+    let mut dev = TcpDevice::listen("127.0.0.1:0").unwrap();
+    let addr = dev.local_addr().unwrap();
+    let first = TcpStream::connect(addr).unwrap();
+    wait_for(&mut dev, CONNECTED);
+    dev.write4(0x7); // half a byte
+    drop(first);
+    wait_for(&mut dev, CLOSED);
+
+    let mut second = TcpStream::connect(addr).unwrap();
+    wait_for(&mut dev, CONNECTED);
+    dev.write4(0x4);
+    dev.write4(0x1);
+    let mut buf = [0u8; 1];
+    second.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"A");
+}
+
+#[test]
+fn full_send_buffer_drops_bytes_and_flags_an_error() {
+    let mut dev = TcpDevice::listen("127.0.0.1:0")
+        .unwrap()
+        .with_tx_capacity(2);
+    for byte in *b"abc" {
+        dev.write4(byte >> 4);
+        dev.write4(byte & 0xF);
+    }
+    assert_eq!(dev.read4(), ERROR, "no peer yet");
+    assert_eq!(dev.take_error().unwrap().kind(), ErrorKind::WouldBlock);
+
+    let mut peer = TcpStream::connect(dev.local_addr().unwrap()).unwrap();
+    wait_for(&mut dev, CONNECTED);
+    peer.set_read_timeout(Some(Duration::from_millis(200)))
+        .unwrap();
+    let mut buf = Vec::new();
+    let _ = peer.read_to_end(&mut buf);
+    assert_eq!(buf, b"ab");
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use intel_4004::bus::simple::SimpleBus;
+use intel_4004::chips::{DataRam4002, Rom4001};
+use intel_4004::dev::shared;
+use intel_4004::dev::terminal::{Capture, Terminal};
+use intel_4004::machine::Machine;
+
+use common::machine;
+
+// RDR; XCH R0 (status) | RDR; XCH R1 (hi) | RDR; XCH R2 (lo)
+const READ_ONE: &[u8] = &[0xEA, 0xB0, 0xEA, 0xB1, 0xEA, 0xB2];
+
+#[test]
+fn rdr_reads_status_then_byte() {
+    let term = shared(Terminal::new());
+    term.borrow_mut().push_input(b"K");
+    let mut m = machine(&term, READ_ONE);
+    m.run_steps(6);
+    assert_eq!(m.cpu().reg(0), 1);
+    assert_eq!(m.cpu().reg(1), 0x4);
+    assert_eq!(m.cpu().reg(2), 0xB);
+}
+
+#[test]
+fn rdr_without_input_keeps_returning_status() {
+    let term = shared(Terminal::new());
+    let mut m = machine(&term, READ_ONE);
+    m.run_steps(2);
+    term.borrow_mut().push_input(b"\n");
+    m.run_steps(4);
+    assert_eq!(m.cpu().reg(0), 0);
+    assert_eq!(m.cpu().reg(1), 1);
+    assert_eq!(m.cpu().reg(2), 0x0);
+}
+
+#[test]
+fn demo_rom_prints_hi() {
+    #[rustfmt::skip]
+    let rom = Rom4001::from_bytes(&[
+        0xD4, 0xE1, 0xD8, 0xE1, // 'H'
+        0xD6, 0xE1, 0xD9, 0xE1, // 'i'
+        0xD0, 0x50, 0x18, 0x40, 0x28,
+    ]);
+    let out = Capture::default();
+    let mut ram = DataRam4002::default();
+    ram.attach_port(Terminal::new().with_sink(out.clone()));
+    let mut m = Machine::new(SimpleBus::new(rom, ram));
+    m.run_steps(35);
+    assert_eq!(out.text(), "Hi");
+}
+
+#[test]
+fn echoes_input_to_sink() {
+    let out = Capture::default();
+    let term = shared(Terminal::new().with_sink(out.clone()));
+    term.borrow_mut().push_input(b"4004");
+    // RDR; JCN 4H,00H | RDR; XCH R0 | RDR; XCH R1 | LD R0; WRR | LD R1; WRR | JUN 000H
+    #[rustfmt::skip]
+    let mut m = machine(&term, &[
+        0xEA, 0x14, 0x00, 0xEA, 0xB0, 0xEA, 0xB1, 0xA0, 0xE2, 0xA1, 0xE2, 0x40, 0x00,
+    ]);
+    m.run_steps(200);
+    assert_eq!(out.text(), "4004");
+}
+
+#[test]
+fn high_bytes_print_as_latin1() {
+    let out = Capture::default();
+    let term = shared(Terminal::new().with_sink(out.clone()));
+    term.borrow_mut().push_input(&[0xE9]);
+    #[rustfmt::skip]
+    let mut m = machine(&term, &[
+        0xEA, 0x14, 0x00, 0xEA, 0xB0, 0xEA, 0xB1, 0xA0, 0xE2, 0xA1, 0xE2, 0x40, 0x00,
+    ]);
+    m.run_steps(20);
+    assert_eq!(out.bytes(), "é".as_bytes());
+}
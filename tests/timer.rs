@@ -0,0 +1,85 @@
+mod common;
+
+use intel_4004::dev::shared;
+use intel_4004::dev::timer::{EXPIRED, OVERRUN, RUNNING, TimerDevice};
+use intel_4004::machine::CLOCK_HZ;
+
+use common::machine;
+
+/// `LDM n; WRR` for each nibble.
+fn writes(nibbles: &[u8]) -> Vec<u8> {
+    nibbles.iter().flat_map(|&n| [0xD0 | n, 0xE2]).collect()
+}
+
+#[test]
+fn one_shot_lowers_test_until_acknowledged() {
+    // SET_PERIOD 0x000040; ONE_SHOT; JCN 9,* (wait for TEST low); ACK; RDR; XCH R0
+    let mut rom = writes(&[1, 0, 0, 0, 0, 4, 0, 6]);
+    let start = 15 * 8;
+    let wait = rom.len() as u8;
+    rom.extend([0x19, wait]);
+    rom.extend(writes(&[4]));
+    rom.extend([0xEA, 0xB0, 0x40, rom.len() as u8 + 2]);
+    let timer = shared(TimerDevice::new());
+    let mut m = machine(&timer, &rom);
+    assert!(m.test_line().get(), "idle high before the timer runs");
+    m.run_until(|cpu| cpu.pc() == wait as u16 + 2);
+    assert!(m.cycles() >= start + 0x40);
+    assert!(m.cycles() < start + 0x40 + 32);
+    assert!(timer.borrow().is_expired());
+    assert!(m.int_line().take());
+    m.run_steps(4);
+    assert!(m.test_line().get());
+    assert!(!m.int_line().get(), "one-shot: raised once");
+    assert_eq!(m.cpu().reg(0), 0);
+    assert!(!timer.borrow().is_running());
+}
+
+#[test]
+fn periodic_timer_reports_overrun() {
+    // SET_PERIOD 0x000020; START; NOP x8; RDR; XCH R0
+    let mut rom = writes(&[1, 0, 0, 0, 0, 2, 0, 2]);
+    rom.extend([0x00; 8]);
+    rom.extend([0xEA, 0xB0]);
+    let timer = shared(TimerDevice::new());
+    let mut m = machine(&timer, &rom);
+    m.run_steps(26);
+    assert_eq!(m.cpu().reg(0), EXPIRED | RUNNING | OVERRUN);
+    assert_eq!(timer.borrow().period(), 0x20);
+    assert!(!m.test_line().get());
+    assert!(m.int_line().get());
+}
+
+#[test]
+fn clock_reads_and_sets_bcd_time() {
+    // READ_TIME; RDR; XCH R0..R5; SET_TIME 23:59:59
+    let mut rom = writes(&[5]);
+    for r in 0..6 {
+        rom.extend([0xEA, 0xB0 | r]);
+    }
+    rom.extend(writes(&[7, 2, 3, 5, 9, 5, 9]));
+    rom.extend([0x40, rom.len() as u8]);
+    let timer = shared(TimerDevice::new().with_time(12, 34, 56));
+    let mut m = machine(&timer, &rom);
+    m.run_steps(2 + 12 + 14);
+    let digits: Vec<u8> = (0..6).map(|r| m.cpu().reg(r)).collect();
+    assert_eq!(digits, [1, 2, 3, 4, 5, 6]);
+    assert_eq!(timer.borrow().time(), (23, 59, 59));
+    // Set when the last WRR started.
+    let set_at = m.cycles() - 8;
+    m.run_until(|cpu| cpu.cycles() >= set_at + CLOCK_HZ - 8);
+    assert_eq!(timer.borrow().time(), (23, 59, 59));
+    m.run_steps(1);
+    assert_eq!(timer.borrow().time(), (0, 0, 0));
+}
+
+#[test]
+fn clock_survives_a_machine_whose_clock_is_behind() {
+    // SET_TIME 10:00:00 after some cycles, then the same device on a fresh machine.
+    let rom = writes(&[7, 1, 0, 0, 0, 0, 0]);
+    let timer = shared(TimerDevice::new());
+    machine(&timer, &rom).run_steps(14);
+    assert_eq!(timer.borrow().time(), (10, 0, 0));
+    machine(&timer, &[0x00]).run_steps(1);
+    assert_eq!(timer.borrow().time(), (10, 0, 0));
+}
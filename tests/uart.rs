@@ -0,0 +1,89 @@
+mod common;
+
+use intel_4004::bus::simple::SimpleBus;
+use intel_4004::chips::{DataRam4002, Rom4001};
+use intel_4004::dev::shared;
+use intel_4004::dev::uart::{Parity, SoftUart, UartConfig, UartError};
+use intel_4004::machine::Machine;
+
+use common::machine;
+
+const CPB: u64 = 16;
+
+/// One `LDM bit; op` pair per bit, 16 cycles each, then a jump to itself.
+fn bit_bang(bits: &[u8], op: u8) -> Vec<u8> {
+    let mut rom = Vec::new();
+    for &bit in bits {
+        rom.extend([0xD0 | bit, op]);
+    }
+    rom.extend([0x40, rom.len() as u8]);
+    rom
+}
+
+#[test]
+fn decodes_bits_written_to_a_ram_port() {
+    let config = UartConfig::with_cycles_per_bit(CPB).with_format(7, Parity::Even, 1);
+    let uart = shared(SoftUart::new(config).with_tx_bit(2));
+    let mut ram = DataRam4002::default();
+    ram.attach_port(uart.clone());
+    // 'C' = 0x43 has three ones: even parity bit is 1. The line is bit 2.
+    let rom = Rom4001::from_bytes(&bit_bang(&[4, 0, 4, 4, 0, 0, 0, 0, 4, 4, 4], 0xE1));
+    let mut m = Machine::new(SimpleBus::new(rom, ram));
+    m.run_steps(26);
+    assert_eq!(uart.borrow_mut().recv(), Some(Ok(b'C')));
+    assert_eq!(uart.borrow_mut().recv(), None);
+}
+
+#[test]
+fn reports_framing_and_parity_errors() {
+    let config = UartConfig::with_cycles_per_bit(CPB).with_format(8, Parity::Odd, 1);
+    let uart = shared(SoftUart::new(config));
+    let mut rom_port = Rom4001::from_bytes(&bit_bang(
+        &[
+            1, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, // 0x03, parity 0: bad
+            0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, // 0x80, parity 0, stop 0
+        ],
+        0xE2,
+    ));
+    rom_port.attach_port(uart.clone());
+    let mut m = Machine::new(SimpleBus::new(rom_port, DataRam4002::default()));
+    m.run_steps(52);
+    assert_eq!(uart.borrow_mut().recv(), Some(Err(UartError::Parity)));
+    assert_eq!(uart.borrow_mut().recv(), Some(Err(UartError::Framing)));
+    assert_eq!(uart.borrow().errors(), 2);
+}
+
+#[test]
+fn sends_on_an_input_bit() {
+    let config = UartConfig::with_cycles_per_bit(CPB);
+    let uart = shared(SoftUart::new(config).with_rx_bit(3));
+    uart.borrow_mut().send(b"5");
+    // Wait for the start bit: RDR; JCN AN,000H. Then RDR; XCH Rn every 16 cycles.
+    let mut rom = vec![0xEA, 0x1C, 0x00];
+    for r in 0..9 {
+        rom.extend([0xEA, 0xB0 | r]);
+    }
+    rom.extend([0x40, rom.len() as u8]);
+    let mut m = machine(&uart, &rom);
+    m.run_steps(20);
+    let byte = (0..8).fold(0, |b, r| b | (m.cpu().reg(r) >> 3) << r);
+    assert_eq!(byte, b'5');
+    assert_eq!(m.cpu().reg(8), 0b1000);
+    assert!(uart.borrow().is_idle());
+}
+
+#[test]
+fn drives_the_test_pin() {
+    let config = UartConfig::with_cycles_per_bit(CPB);
+    let mut m = machine(&shared(SoftUart::new(config)), &[0x40, 0x00]);
+    let uart = shared(SoftUart::new(config).with_test(m.test_line()));
+    m.bus_mut().data.attach_port(uart.clone());
+    uart.borrow_mut().send(&[0b1010_0011]);
+    // JUN is 16 cycles: one sample per bit.
+    let mut levels = Vec::new();
+    for _ in 0..11 {
+        m.step();
+        levels.push(m.test_line().get() as u8);
+    }
+    assert_eq!(levels, [0, 1, 1, 0, 0, 0, 1, 0, 1, 1, 1]);
+}
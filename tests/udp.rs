@@ -0,0 +1,208 @@
+mod common;
+
+use std::io::ErrorKind;
+use std::net::UdpSocket;
+use std::time::{Duration, Instant};
+
+use intel_4004::bus::simple::SimpleBus;
+use intel_4004::dev::udp::{AVAILABLE, DATA, ERROR, FULL, UdpDevice, UdpStats};
+use intel_4004::dev::{IoDevice, Shared, shared};
+use intel_4004::machine::Machine;
+
+use common::machine;
+
+/// A peer socket and a shared `UdpDevice` connected to it.
+fn pair(dev: impl FnOnce(UdpDevice) -> UdpDevice) -> (UdpSocket, Shared<UdpDevice>) {
+    let peer = UdpSocket::bind("127.0.0.1:0").unwrap();
+    peer.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    let device = UdpDevice::new("127.0.0.1:0", &peer.local_addr().unwrap().to_string()).unwrap();
+    peer.connect(device.local_addr().unwrap()).unwrap();
+    (peer, shared(dev(device)))
+}
+
+fn regs(m: &Machine<SimpleBus>, n: u8) -> Vec<u8> {
+    (0..n).map(|r| m.cpu().reg(r)).collect()
+}
+
+// 000: RDR; RAR; JCN NC,000H → wait for a datagram
+// 004: LDM 0; WRR; WRR → receive command
+// 007: RDR → status
+// 008: RDR; XCH R0 … RDR; XCH R5 → len hi, len lo, 2 payload bytes
+#[rustfmt::skip]
+const RECV_2: &[u8] = &[
+    0xEA, 0xF6, 0x1A, 0x00,
+    0xD0, 0xE2, 0xE2,
+    0xEA,
+    0xEA, 0xB0, 0xEA, 0xB1, 0xEA, 0xB2, 0xEA, 0xB3, 0xEA, 0xB4, 0xEA, 0xB5,
+    0x40, 0x14,
+];
+
+// RECV_2, then the same at 014 into R6–R11
+#[rustfmt::skip]
+const RECV_TWO: &[u8] = &[
+    0xEA, 0xF6, 0x1A, 0x00,
+    0xD0, 0xE2, 0xE2,
+    0xEA,
+    0xEA, 0xB0, 0xEA, 0xB1, 0xEA, 0xB2, 0xEA, 0xB3, 0xEA, 0xB4, 0xEA, 0xB5,
+    0xEA, 0xF6, 0x1A, 0x14,
+    0xD0, 0xE2, 0xE2,
+    0xEA,
+    0xEA, 0xB6, 0xEA, 0xB7, 0xEA, 0xB8, 0xEA, 0xB9, 0xEA, 0xBA, 0xEA, 0xBB,
+    0x40, 0x28,
+];
+
+// LDM 0; WRR; LDM 1; WRR (len = 1) | LDM 0; WRR; WRR (00H) | JUN 000H
+const SEND_FOREVER: &[u8] = &[0xD0, 0xE2, 0xD1, 0xE2, 0xD0, 0xE2, 0xE2, 0x40, 0x00];
+
+#[test]
+fn sends_datagram() {
+    let (peer, dev) = pair(|d| d);
+    // len = 2 | 'H' | 'i'
+    #[rustfmt::skip]
+    let mut m = machine(&dev, &[
+        0xD0, 0xE2, 0xD2, 0xE2,
+        0xD4, 0xE2, 0xD8, 0xE2,
+        0xD6, 0xE2, 0xD9, 0xE2,
+    ]);
+    m.run_steps(12);
+    let mut buf = [0u8; 16];
+    let n = peer.recv(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"Hi");
+}
+
+#[test]
+fn receives_datagram_through_rdr() {
+    let (peer, dev) = pair(|d| d);
+    let mut m = machine(&dev, RECV_2);
+    peer.send(b"OK").unwrap();
+    m.run_until(|cpu| cpu.pc() == 0x014);
+    assert_eq!(regs(&m, 6), [0x0, 0x2, 0x4, 0xF, 0x4, 0xB]);
+}
+
+#[test]
+fn datagrams_are_read_in_order() {
+    let (peer, dev) = pair(|d| d);
+    let mut m = machine(&dev, RECV_TWO);
+    peer.send(b"ab").unwrap();
+    peer.send(b"cd").unwrap();
+    m.run_until(|cpu| cpu.pc() == 0x028);
+    assert_eq!(regs(&m, 12)[2..6], [0x6, 0x1, 0x6, 0x2]);
+    assert_eq!(regs(&m, 12)[8..], [0x6, 0x3, 0x6, 0x4]);
+}
+
+#[test]
+fn full_queue_drops_new_datagrams() {
+    let (peer, dev) = pair(|d| d.with_rx_capacity(1));
+    let mut m = machine(&dev, RECV_TWO);
+    peer.send(b"xy").unwrap();
+    peer.send(b"zz").unwrap();
+    m.run_until(|cpu| cpu.pc() == 0x014);
+    assert_eq!(regs(&m, 6)[2..], [0x7, 0x8, 0x7, 0x9]);
+    m.run_steps(1000);
+    assert!((0x014..0x018).contains(&m.cpu().pc()));
+}
+
+#[test]
+fn status_reads_leave_datagrams_queued_until_received() {
+    let (peer, dev) = pair(|d| d);
+    peer.send(b"OK").unwrap();
+    let mut dev = dev.borrow_mut();
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while dev.read4() != AVAILABLE {
+        assert!(Instant::now() < deadline, "no datagram");
+    }
+    assert_eq!(dev.read4(), AVAILABLE, "polling the status again");
+
+    // A zero length is the receive command, not a datagram.
+    dev.write4(0);
+    dev.write4(0);
+    assert_eq!(dev.read4(), DATA);
+    let nibbles: Vec<u8> = (0..6).map(|_| dev.read4()).collect();
+    assert_eq!(nibbles, [0x0, 0x2, 0x4, 0xF, 0x4, 0xB]);
+    assert_eq!(dev.read4(), 0);
+    assert_eq!(dev.stats().sent, 0);
+}
+
+#[test]
+fn counts_frames() {
+    let (peer, dev) = pair(|d| d);
+    peer.send(b"1").unwrap();
+
+    // len = 1, 'A' | RDR
+    let mut m = machine(
+        &dev,
+        &[0xD0, 0xE2, 0xD1, 0xE2, 0xD4, 0xE2, 0xD1, 0xE2, 0xEA],
+    );
+    m.run_steps(9);
+    assert_eq!(
+        dev.borrow().stats(),
+        UdpStats {
+            sent: 1,
+            failed: 0,
+            received: 1,
+            rx_dropped: 0,
+        }
+    );
+}
+
+#[test]
+fn send_errors_are_reported_not_raised() {
+    let gone = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = gone.local_addr().unwrap().to_string();
+    drop(gone);
+    let dev = shared(UdpDevice::new("127.0.0.1:0", &addr).unwrap());
+
+    // The first datagram draws an ICMP port unreachable, which fails a later send.
+    let mut m = machine(&dev, SEND_FOREVER);
+    m.run_steps(1_000);
+    let mut dev = dev.borrow_mut();
+    let stats = dev.stats();
+    assert!(stats.sent >= 1 && stats.failed >= 1, "{stats:?}");
+    assert_eq!(dev.read4() & ERROR, ERROR);
+    assert_eq!(
+        dev.take_error().unwrap().kind(),
+        ErrorKind::ConnectionRefused
+    );
+}
+
+#[test]
+fn cycle_interval_spaces_out_sends() {
+    let (_peer, dev) = pair(|d| d.with_cycle_interval(1000));
+    let mut m = machine(&dev, SEND_FOREVER);
+    m.run_until(|cpu| cpu.cycles() >= 10_000);
+    let mut dev = dev.borrow_mut();
+    assert!((10..=11).contains(&dev.stats().sent), "{:?}", dev.stats());
+    assert!(dev.pending() > 16, "nothing is dropped");
+    assert_eq!(dev.read4() & FULL, FULL);
+}
+
+#[test]
+fn rom_waits_while_the_send_queue_is_full() {
+    // 000: RDR; RAR; RAR; JCN C,000H → wait while FULL
+    // 005: LDM 0; WRR; LDM 1; WRR; LDM 0; WRR; WRR; JUN 000H
+    #[rustfmt::skip]
+    let rom = [
+        0xEA, 0xF6, 0xF6, 0x12, 0x00,
+        0xD0, 0xE2, 0xD1, 0xE2, 0xD0, 0xE2, 0xE2, 0x40, 0x00,
+    ];
+    let (peer, dev) = pair(|d| d.with_cycle_interval(1000).with_tx_capacity(4));
+    // Polling the status must not take it, or the writes would get out of step.
+    peer.send(b"never received").unwrap();
+    let mut m = machine(&dev, &rom);
+    m.run_until(|cpu| cpu.cycles() >= 10_000);
+    assert!((10..=11).contains(&dev.borrow().stats().sent));
+    assert_eq!(dev.borrow().pending(), 4);
+    let mut buf = [0u8; 16];
+    assert_eq!(peer.recv(&mut buf).unwrap(), 1);
+    assert_eq!(buf[0], 0x00);
+}
+
+#[test]
+fn host_interval_does_not_block_the_machine() {
+    let (_peer, dev) = pair(|d| d.with_interval(Duration::from_secs(3600)));
+    let mut m = machine(&dev, SEND_FOREVER);
+    assert_eq!(m.scheduler().timers(), 1);
+    m.run_steps(10_000);
+    assert_eq!(dev.borrow().stats().sent, 1);
+    assert_eq!(dev.borrow().pending(), 1_249); // 8 instructions per frame
+}